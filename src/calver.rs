@@ -0,0 +1,370 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use crate::implementations::{from_radix, Version};
+
+// https://calver.org/#scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Token {
+  FullYear,    // YYYY, 2006
+  ShortYear,   // YY, 6 16 106
+  PaddedYear,  // 0Y, 06 16 106
+  Month,       // MM, 1 12
+  PaddedMonth, // 0M, 01 12
+  Week,        // WW, 1 53
+  PaddedWeek,  // 0W, 01 53
+  Day,         // DD, 1 31
+  PaddedDay,   // 0D, 01 31
+  Major,       // MAJOR
+  Minor,       // MINOR
+  Micro,       // MICRO
+}
+
+// YYYY.MM.MICRO, YY.0M
+// every version can also have a -modifier at the end (2024.10.17-1, 24.04-rc1)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CalVerFormat {
+  pub tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalVer {
+  pub format: CalVerFormat,
+  // one per token
+  pub values: Vec<u32>,
+  pub modifier: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+  pub year: u32,
+  pub month: u32,
+  pub day: u32,
+}
+
+/// Where bump gets today's date from, SystemClock unless testing
+pub trait Clock {
+  fn today(&self) -> Date;
+}
+
+/// Today in UTC
+pub struct SystemClock;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CalVerError {
+  #[error("invalid calver format: {0}")]
+  InvalidFormat(String),
+  #[error("{0} doesn't match the format {1}")]
+  InvalidVersion(String, String),
+  #[error("invalid requirement: {0}")]
+  InvalidRequirement(String),
+  #[error("invalid date in {0}")]
+  InvalidDate(String),
+  #[error("{0} has no MAJOR, MINOR or MICRO to increment on the same date")]
+  NoCounter(String),
+  #[error("{0} is newer than today ({1})")]
+  FromTheFuture(String, Date),
+  #[error("{0} can't be represented as a Version")]
+  Unrepresentable(String),
+}
+
+impl Token {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "YYYY" => Some(Self::FullYear),
+      "YY" => Some(Self::ShortYear),
+      "0Y" => Some(Self::PaddedYear),
+      "MM" => Some(Self::Month),
+      "0M" => Some(Self::PaddedMonth),
+      "WW" => Some(Self::Week),
+      "0W" => Some(Self::PaddedWeek),
+      "DD" => Some(Self::Day),
+      "0D" => Some(Self::PaddedDay),
+      "MAJOR" => Some(Self::Major),
+      "MINOR" => Some(Self::Minor),
+      "MICRO" => Some(Self::Micro),
+      _ => None
+    }
+  }
+
+  fn is_date(&self) -> bool {
+    !matches!(self, Self::Major | Self::Minor | Self::Micro)
+  }
+
+  fn is_padded(&self) -> bool {
+    matches!(self, Self::PaddedYear | Self::PaddedMonth | Self::PaddedWeek | Self::PaddedDay)
+  }
+
+  fn valid(&self, value: u32) -> bool {
+    match self {
+      Self::FullYear => (1000..=9999).contains(&value),
+      Self::Month | Self::PaddedMonth => (1..=12).contains(&value),
+      Self::Week | Self::PaddedWeek => (1..=53).contains(&value),
+      Self::Day | Self::PaddedDay => (1..=31).contains(&value),
+      _ => true,
+    }
+  }
+
+  fn value_on(&self, date: &Date) -> u32 {
+    match self {
+      Self::FullYear => date.year,
+      Self::ShortYear | Self::PaddedYear => date.year.saturating_sub(2000),
+      Self::Month | Self::PaddedMonth => date.month,
+      Self::Week | Self::PaddedWeek => date.week(),
+      Self::Day | Self::PaddedDay => date.day,
+      Self::Major | Self::Minor | Self::Micro => 0,
+    }
+  }
+}
+
+impl Display for Token {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::FullYear => "YYYY",
+      Self::ShortYear => "YY",
+      Self::PaddedYear => "0Y",
+      Self::Month => "MM",
+      Self::PaddedMonth => "0M",
+      Self::Week => "WW",
+      Self::PaddedWeek => "0W",
+      Self::Day => "DD",
+      Self::PaddedDay => "0D",
+      Self::Major => "MAJOR",
+      Self::Minor => "MINOR",
+      Self::Micro => "MICRO",
+    })
+  }
+}
+
+impl CalVerFormat {
+  pub fn parse<S: Into<String>>(format: S) -> Result<Self, CalVerError> {
+    let format = format.into();
+    let tokens = format.split('.')
+      .map(Token::from_name)
+      .collect::<Option<Vec<_>>>()
+      .ok_or_else(|| CalVerError::InvalidFormat(format.clone()))?;
+    if !tokens.iter().any(|t| t.is_date()) {
+      return Err(CalVerError::InvalidFormat(format));
+    }
+    Ok(Self { tokens })
+  }
+
+  fn date_values(&self, date: &Date) -> Vec<u32> {
+    self.tokens.iter().filter(|t| t.is_date()).map(|t| t.value_on(date)).collect()
+  }
+}
+
+impl Display for CalVerFormat {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let s = self.tokens.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(".");
+    write!(f, "{}", s)
+  }
+}
+
+impl FromStr for CalVerFormat {
+  type Err = CalVerError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+impl Date {
+  pub fn new(year: u32, month: u32, day: u32) -> Self {
+    Self { year, month, day }
+  }
+
+  fn is_leap(year: u32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+  }
+
+  fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+      2 if Self::is_leap(year) => 29,
+      2 => 28,
+      4 | 6 | 9 | 11 => 30,
+      _ => 31,
+    }
+  }
+
+  /// Week of the year, days 1-7 are week 1
+  pub fn week(&self) -> u32 {
+    let ordinal: u32 = (1..self.month).map(|m| Self::days_in_month(self.year, m)).sum::<u32>() + self.day;
+    (ordinal - 1) / 7 + 1
+  }
+
+  // days since 1970-01-01 to a date, http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  fn from_days(days: i64) -> Self {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as u32;
+    Self { year, month, day }
+  }
+}
+
+impl Display for Date {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+  }
+}
+
+impl Clock for SystemClock {
+  fn today(&self) -> Date {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Date::from_days((seconds / 86400) as i64)
+  }
+}
+
+impl CalVer {
+  pub fn parse<S: Into<String>>(version: S, format: &CalVerFormat) -> Result<Self, CalVerError> {
+    let version = version.into();
+    let invalid = || CalVerError::InvalidVersion(version.clone(), format.to_string());
+    let (numbers, modifier) = match version.split_once('-') {
+      Some((_, "")) => return Err(invalid()),
+      Some((numbers, modifier)) => (numbers, Some(modifier.to_string())),
+      None => (version.as_str(), None),
+    };
+    let parts: Vec<&str> = numbers.split('.').collect();
+    if parts.len() != format.tokens.len() {
+      return Err(invalid());
+    }
+    let mut values = vec![];
+    for (part, token) in parts.iter().zip(&format.tokens) {
+      if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+      }
+      // 0M needs 04, MM needs 4
+      let leading_zero = part.len() > 1 && part.starts_with('0');
+      if (token.is_padded() && part.len() < 2) || (!token.is_padded() && leading_zero) {
+        return Err(invalid());
+      }
+      values.push(part.parse().map_err(|_| invalid())?);
+    }
+    let calver = Self { format: format.clone(), values, modifier };
+    calver.validate().map_err(|_| CalVerError::InvalidDate(version.clone()))?;
+    Ok(calver)
+  }
+
+  fn value(&self, token: Token) -> Option<u32> {
+    self.format.tokens.iter().position(|t| *t == token).map(|i| self.values[i])
+  }
+
+  fn validate(&self) -> Result<(), CalVerError> {
+    let invalid = || CalVerError::InvalidDate(self.to_string());
+    for (token, value) in self.format.tokens.iter().zip(&self.values) {
+      if !token.valid(*value) {
+        return Err(invalid());
+      }
+    }
+    // the day has to exist in that month, if we know the month (and year for february)
+    let year = self.value(Token::FullYear)
+      .or(self.value(Token::ShortYear).or(self.value(Token::PaddedYear)).map(|y| y + 2000));
+    let month = self.value(Token::Month).or(self.value(Token::PaddedMonth));
+    let day = self.value(Token::Day).or(self.value(Token::PaddedDay));
+    if let (Some(month), Some(day)) = (month, day) {
+      if day > Date::days_in_month(year.unwrap_or(2000), month) {
+        return Err(invalid());
+      }
+    }
+    Ok(())
+  }
+
+  /// Version for range matching: the first three values are major, minor and patch,
+  /// a 4th is the extra_version and the modifier is the pre_release. 2024.10.17-1 -> 2024.10.17-1
+  /// Fails on a 5th value (2024.10.17.12.3 isn't 2024.10.17.123) and on modifiers a pre_release
+  /// can't hold (24.04-rc.1 would become the release itself)
+  pub fn to_version(&self) -> Result<Version, CalVerError> {
+    let unrepresentable = || CalVerError::Unrepresentable(self.to_string());
+    if self.values.len() > 4 {
+      return Err(unrepresentable());
+    }
+    let number = |i: usize| self.values.get(i).copied().unwrap_or(0);
+    let extra = self.values.get(3).map(|n| n.to_string());
+    Version::new_w_extra(number(0), number(1), number(2), extra, self.modifier.clone(), None).map_err(|_| unrepresentable())
+  }
+
+  /// Reads a Version back with a format, 2024.4.0 with YYYY.0M.MICRO -> 2024.04.0
+  pub fn from_version(version: &Version, format: &CalVerFormat) -> Result<Self, CalVerError> {
+    let invalid = || CalVerError::InvalidVersion(version.to_string(), format.to_string());
+    let mut values = vec![version.major, version.minor, version.patch];
+    if let Some(extra) = &version.extra_version {
+      // extra only keeps one number
+      values.push(from_radix(extra).parse().map_err(|_| invalid())?);
+    }
+    // missing values are 0, like in Version, but there can't be more than the format has
+    if values.len() > format.tokens.len() && values[format.tokens.len()..].iter().any(|v| *v != 0) {
+      return Err(invalid());
+    }
+    values.resize(format.tokens.len(), 0);
+    let calver = Self {
+      format: format.clone(),
+      values,
+      modifier: version.pre_release.as_ref().map(from_radix),
+    };
+    calver.validate()?;
+    Ok(calver)
+  }
+
+  /// Next version from today's date: a new date resets MAJOR, MINOR and MICRO to 0,
+  /// the same date increments the last of them. The modifier is dropped.
+  pub fn bump(&self) -> Result<Self, CalVerError> {
+    self.bump_with(&SystemClock)
+  }
+
+  pub fn bump_with<C: Clock>(&self, clock: &C) -> Result<Self, CalVerError> {
+    let today = clock.today();
+    let current: Vec<u32> = self.format.tokens.iter().zip(&self.values)
+      .filter(|(t, _)| t.is_date())
+      .map(|(_, v)| *v)
+      .collect();
+    let values = match self.format.date_values(&today).cmp(&current) {
+      Ordering::Less => return Err(CalVerError::FromTheFuture(self.to_string(), today)),
+      Ordering::Greater => self.format.tokens.iter().map(|t| t.value_on(&today)).collect(),
+      Ordering::Equal => {
+        let last = self.format.tokens.iter().rposition(|t| !t.is_date())
+          .ok_or_else(|| CalVerError::NoCounter(self.to_string()))?;
+        let mut values = self.values.clone();
+        values[last] += 1;
+        values
+      }
+    };
+    Ok(Self { format: self.format.clone(), values, modifier: None })
+  }
+}
+
+impl Display for CalVer {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let s = self.format.tokens.iter().zip(&self.values)
+      .map(|(t, v)| if t.is_padded() { format!("{:02}", v) } else { v.to_string() })
+      .collect::<Vec<_>>()
+      .join(".");
+    write!(f, "{}", s)?;
+    if let Some(modifier) = &self.modifier {
+      write!(f, "-{}", modifier)?;
+    }
+    Ok(())
+  }
+}
+
+impl PartialOrd for CalVer {
+  // only versions with the same format can be compared, the modifier makes it older like a pre-release
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    if self.format != other.format {
+      return None;
+    }
+    Some(self.values.cmp(&other.values).then_with(|| match (&self.modifier, &other.modifier) {
+      (None, None) => Ordering::Equal,
+      (None, Some(_)) => Ordering::Greater,
+      (Some(_), None) => Ordering::Less,
+      (Some(a), Some(b)) => a.cmp(b),
+    }))
+  }
+}
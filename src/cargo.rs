@@ -0,0 +1,225 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+use crate::{Dependency, ParseError};
+use crate::implementations::{Op, Range};
+use crate::written::{caret_max, tilde_max, Written};
+
+// the dependency tables of a Cargo.toml, dev_dependencies and build_dependencies are the old spellings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Table {
+  Dependencies,
+  DevDependencies,
+  BuildDependencies,
+  // [workspace.dependencies], what members inherit with foo = { workspace = true }
+  Workspace,
+}
+
+/// A dependency with a version requirement and where it was found.
+/// Path, git and inherited dependencies without a version aren't listed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CargoDependency {
+  // the name is the crate's, package = "..." when it's renamed
+  pub dependency: Dependency,
+  // the name in the manifest, what the code uses
+  pub key: String,
+  pub table: Table,
+  // cfg(windows), x86_64-pc-windows-gnu, ... for [target.'cfg(windows)'.dependencies]
+  pub target: Option<String>,
+  // as written, "1.2" is ^1.2 in Cargo
+  pub requirement: String,
+}
+
+/// A Cargo.toml that keeps its formatting and comments, so it can be edited and written back
+#[derive(Debug, Clone)]
+pub struct Manifest {
+  document: DocumentMut,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CargoError {
+  #[error("invalid Cargo.toml: {0}")]
+  Toml(String),
+  #[error("{name} has an invalid version requirement: {requirement}")]
+  InvalidRequirement { name: String, requirement: String },
+  #[error("{0} can't be written as a Cargo version requirement")]
+  Unrepresentable(String),
+  #[error("{0} isn't in the manifest")]
+  NotFound(String),
+}
+
+impl Table {
+  fn sections(&self) -> &'static [&'static str] {
+    match self {
+      Self::Dependencies => &["dependencies"],
+      Self::DevDependencies => &["dev-dependencies", "dev_dependencies"],
+      Self::BuildDependencies => &["build-dependencies", "build_dependencies"],
+      Self::Workspace => &["dependencies"],
+    }
+  }
+}
+
+impl Manifest {
+  pub fn parse<S: Into<String>>(manifest: S) -> Result<Self, CargoError> {
+    let document = manifest.into().parse::<DocumentMut>().map_err(|e| CargoError::Toml(e.message().to_string()))?;
+    Ok(Self { document })
+  }
+
+  /// Every dependency with a version, in the order of the file's tables: the package's, then each target's, then the workspace's
+  pub fn dependencies(&self) -> Result<Vec<CargoDependency>, CargoError> {
+    let root = self.document.as_table();
+    let mut dependencies = vec![];
+    for table in [Table::Dependencies, Table::DevDependencies, Table::BuildDependencies] {
+      for section in table.sections() {
+        read_table(root.get(section), table, None, &mut dependencies)?;
+      }
+    }
+    if let Some(targets) = root.get("target").and_then(Item::as_table_like) {
+      for (target, item) in targets.iter() {
+        for table in [Table::Dependencies, Table::DevDependencies, Table::BuildDependencies] {
+          for section in table.sections() {
+            let item = item.as_table_like().and_then(|t| t.get(section));
+            read_table(item, table, Some(target), &mut dependencies)?;
+          }
+        }
+      }
+    }
+    let workspace = root.get("workspace").and_then(Item::as_table_like).and_then(|w| w.get("dependencies"));
+    read_table(workspace, Table::Workspace, None, &mut dependencies)?;
+    Ok(dependencies)
+  }
+
+  /// Writes range as the requirement of dependency, keeping everything else in the file as it was.
+  /// A requirement written without an operator stays without one (1.2 -> 1.4.0).
+  pub fn set_requirement(&mut self, dependency: &CargoDependency, range: &Range) -> Result<(), CargoError> {
+    let bare = dependency.requirement.trim_start().starts_with(|c: char| c.is_ascii_digit());
+    let requirement = to_requirement(range, bare).ok_or_else(|| CargoError::Unrepresentable(range.to_string()))?;
+    let not_found = || CargoError::NotFound(dependency.key.clone());
+    let table = self.table_mut(dependency).ok_or_else(not_found)?;
+    let entry = table.get_mut(&dependency.key).ok_or_else(not_found)?;
+    let item = match entry.as_table_like_mut() {
+      Some(entry) => entry.get_mut("version").ok_or_else(not_found)?,
+      None => entry,
+    };
+    let value = item.as_value_mut().ok_or_else(not_found)?;
+    // the spaces and comments around the value
+    let decor = value.decor().clone();
+    *value = Value::from(requirement);
+    *value.decor_mut() = decor;
+    Ok(())
+  }
+
+  fn table_mut(&mut self, dependency: &CargoDependency) -> Option<&mut dyn TableLike> {
+    let mut item = self.document.as_item_mut();
+    match (&dependency.table, &dependency.target) {
+      (Table::Workspace, _) => item = item.as_table_like_mut()?.get_mut("workspace")?,
+      (_, Some(target)) => item = item.as_table_like_mut()?.get_mut("target")?.as_table_like_mut()?.get_mut(target)?,
+      _ => {}
+    }
+    let table = item.as_table_like_mut()?;
+    let section = dependency.table.sections().iter()
+      .find(|s| table.get(s).and_then(Item::as_table_like).is_some_and(|t| t.contains_key(&dependency.key)))?;
+    table.get_mut(section)?.as_table_like_mut()
+  }
+}
+
+fn read_table(item: Option<&Item>, table: Table, target: Option<&str>, dependencies: &mut Vec<CargoDependency>) -> Result<(), CargoError> {
+  let Some(entries) = item.and_then(Item::as_table_like) else { return Ok(()) };
+  for (key, entry) in entries.iter() {
+    // foo = "1.2" or foo = { version = "1.2", package = "bar" }
+    let (requirement, package) = match entry.as_table_like() {
+      Some(entry) => (entry.get("version").and_then(Item::as_str), entry.get("package").and_then(Item::as_str)),
+      None => (entry.as_str(), None),
+    };
+    let Some(requirement) = requirement else { continue };
+    let name = package.unwrap_or(key).to_string();
+    let range = parse_requirement(requirement).map_err(|_| CargoError::InvalidRequirement { name: name.clone(), requirement: requirement.to_string() })?;
+    dependencies.push(CargoDependency {
+      dependency: Dependency { name, range },
+      key: key.to_string(),
+      table,
+      target: target.map(|t| t.to_string()),
+      requirement: requirement.to_string(),
+    });
+  }
+  Ok(())
+}
+
+// the range as Cargo would write it, None if it has != or || in it
+fn to_requirement(range: &Range, bare: bool) -> Option<String> {
+  if !range.except.is_empty() {
+    return None;
+  }
+  match (&range.min, &range.max, range.include.as_slice()) {
+    (None, None, []) => Some("*".to_string()),
+    (None, None, [version]) => Some(format!("={}", version)),
+    (_, _, [_, ..]) => None,
+    (Some(min), Some(max), []) if Some(max) == caret_max(min).as_ref() => Some(format!("{}{}", if bare { "" } else { "^" }, min)),
+    (Some(min), Some(max), []) if Some(max) == tilde_max(min).as_ref() => Some(format!("~{}", min)),
+    (Some(min), Some(max), []) => Some(format!(">={}, <{}", min, max)),
+    (Some(min), None, []) => Some(format!(">={}", min)),
+    (None, Some(max), []) => Some(format!("<{}", max)),
+  }
+}
+
+impl Display for Manifest {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.document)
+  }
+}
+
+impl FromStr for Manifest {
+  type Err = CargoError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+/// A Cargo version requirement ("1.2", "~1.2.3", ">=1.2, <1.5", "1.*") as a Range.
+/// A version without an operator is ^, and ^ keeps the first number that isn't 0: ^0.3 -> >=0.3.0 <0.4.0
+pub fn parse_requirement<S: Into<String>>(requirement: S) -> Result<Range, ParseError> {
+  cargo_parser::parse_requirement(&requirement.into())
+}
+
+// what op means in front of the version as Cargo reads it
+fn with_op(written: &Written, op: &str) -> Result<Range, &'static str> {
+  let partial = written.is_partial();
+  Ok(match op {
+    _ if written.wildcard && matches!(op, "" | "^" | "=") => written.between(Written::next(&written.numbers)?)?,
+    // ^0 and ^0.0 don't go to the first number that isn't 0, it isn't written
+    "" | "^" => written.caret()?,
+    "~" => written.between(Written::next(&written.numbers[..written.numbers.len().min(2)])?)?,
+    "=" if partial => written.between(Written::next(&written.numbers)?)?,
+    ">" if partial => Range::from_ver_vec(vec![(Op::Ge, Written::next(&written.numbers)?)]),
+    "<=" if partial => Range::from_ver_vec(vec![(Op::Lt, Written::next(&written.numbers)?)]),
+    _ => Range::from_ver_vec(vec![(Op::from_str(op).unwrap(), written.to_version()?)]),
+  })
+}
+
+peg::parser!( grammar cargo_parser() for str {
+  rule _() = [' ' | '\t']*
+
+  rule num() -> u32
+    = n:$(['0'..='9']+) {? n.parse().or(Err("number")) }
+
+  // alpha.1 -> alpha1, dots and hyphens can't be stored
+  rule identifiers() -> String
+    = i:$(['a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-']+) { i.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase() }
+
+  rule version() -> Written
+    = n:(num() **<1,3> ".") w:("." ['*' | 'x' | 'X'])? p:("-" p:identifiers() { p })? ("+" identifiers())? {
+      Written { numbers: n, wildcard: w.is_some(), pre: p.filter(|p| !p.is_empty()) }
+    }
+
+  rule op() -> &'input str
+    = $(">=" / "<=" / "=" / ">" / "<" / "~" / "^" / "")
+
+  rule comparator() -> Range
+    = ['*' | 'x' | 'X'] { Range::any() }
+    / o:op() _ v:version() {? with_op(&v, o) }
+
+  pub rule parse_requirement() -> Range
+    = _ c:(comparator() ++ (_ "," _)) _ ![_] { c.iter().fold(Range::any(), |range, c| range.intersect(c)) }
+});
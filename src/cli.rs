@@ -0,0 +1,338 @@
+use std::cmp::Ordering;
+use std::io::{BufRead, Write};
+use crate::diagnostics;
+use crate::implementations::{from_radix, Range, Version};
+use crate::outdated::Change;
+use crate::scheme::{SemVer, VersionScheme};
+
+/// Exit codes: it worked (or the answer is yes), the answer is no, and the arguments or input were wrong
+pub const SUCCESS: u8 = 0;
+pub const NO: u8 = 1;
+pub const USAGE: u8 = 2;
+
+pub const HELP: &str = "\
+usage: yasemver <command> [options] [arguments]
+
+  parse <version>...                  the fields of each version as JSON, one object per line
+  compare <a> <b>                     -1, 0 or 1, pre-releases are older than releases
+  satisfies <range> <version>...      the versions in range, exits 1 if any isn't
+  sort [--reverse] [--unique]         the versions in stdin, oldest first
+  max-satisfying <range> [version...] the newest version in range, from the arguments or stdin, exits 1 if there's none
+  bump <major|minor|patch> <version>  the next version, 1.2.3-rc1 bumps to 1.2.3 with patch
+  diff <a> <b>                        major, minor, patch or pre-release, exits 1 if they're the same
+  normalize-range <range>             the range as yasemver writes it, => and ~> are taken
+  filter <range>                      the versions in stdin that are in range, as they come, exits 1 if there's none
+    --prerelease[=include|exclude|only]  what to do with pre-releases, they're excluded by default
+    --invalid=skip|error|pass         what to do with lines that aren't versions, they're skipped by default
+
+exit codes: 0 yes or done, 1 no, 2 invalid arguments or input";
+
+// the arguments that aren't --flags, and the flags
+struct Arguments {
+  positional: Vec<String>,
+  flags: Vec<String>,
+}
+
+impl Arguments {
+  fn new(args: &[String]) -> Self {
+    let (flags, positional) = args.iter().cloned().partition(|a| a.starts_with("--") && a.len() > 2);
+    Self { positional, flags }
+  }
+
+  fn flag(&self, name: &str) -> bool {
+    self.flags.iter().any(|f| f == name)
+  }
+
+  // --name=value, the last one wins. None without the flag, Some("") when it's only --name
+  fn value(&self, name: &str) -> Option<&str> {
+    self.flags.iter().rev().find_map(|f| match f.strip_prefix(name)? {
+      "" => Some(""),
+      value => value.strip_prefix('='),
+    })
+  }
+
+  // every flag has to be one of known, values aren't checked
+  fn only(&self, known: &[&str]) -> Result<(), String> {
+    match self.flags.iter().find(|f| !known.contains(&f.split('=').next().unwrap_or(f))) {
+      Some(flag) => Err(format!("unknown option {}", flag)),
+      None => Ok(()),
+    }
+  }
+
+  fn exactly(&self, n: usize, usage: &str) -> Result<&[String], String> {
+    match self.positional.len() == n {
+      true => Ok(&self.positional),
+      false => Err(format!("usage: yasemver {}", usage)),
+    }
+  }
+}
+
+fn version(text: &str) -> Result<Version, String> {
+  Version::parse(text).map_err(|e| format!("invalid version\n{}", diagnostics::render(text, &e)))
+}
+
+fn range(text: &str) -> Result<Range, String> {
+  Range::parse(text).map_err(|e| format!("invalid range\n{}", diagnostics::render(text, &e)))
+}
+
+// the non-empty lines of input, with their line number
+fn lines<R: BufRead>(input: R) -> impl Iterator<Item = Result<(usize, String), String>> {
+  input.lines().enumerate()
+    .map(|(i, line)| line.map(|l| (i + 1, l.trim().to_string())).map_err(|e| e.to_string()))
+    .filter(|line| !matches!(line, Ok((_, l)) if l.is_empty()))
+}
+
+// the versions of input, as written and read
+fn read_versions<R: BufRead>(input: R) -> Result<Vec<(String, Version)>, String> {
+  lines(input).map(|line| {
+    let (i, text) = line?;
+    let version = version(&text).map_err(|e| format!("line {}: {}", i, e))?;
+    Ok((text, version))
+  }).collect()
+}
+
+fn json_string(s: Option<&str>) -> String {
+  match s {
+    None => "null".to_string(),
+    Some(s) => {
+      let mut escaped = String::from("\"");
+      for c in s.chars() {
+        match c {
+          '"' => escaped.push_str("\\\""),
+          '\\' => escaped.push_str("\\\\"),
+          c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+          c => escaped.push(c),
+        }
+      }
+      escaped.push('"');
+      escaped
+    }
+  }
+}
+
+// {"version":"1.2.3-rc1","major":1,"minor":2,"patch":3,"extra_version":null,"pre_release":"rc1","build":null}
+fn to_json(version: &Version) -> String {
+  format!(
+    "{{\"version\":{},\"major\":{},\"minor\":{},\"patch\":{},\"extra_version\":{},\"pre_release\":{},\"build\":{}}}",
+    json_string(Some(&version.to_string())), version.major, version.minor, version.patch,
+    json_string(version.extra_version.as_ref().map(from_radix).as_deref()),
+    json_string(version.pre_release.as_ref().map(from_radix).as_deref()),
+    json_string(version.build.as_deref()),
+  )
+}
+
+/// 1.2.3 -> 2.0.0, 1.3.0 or 1.2.4. A pre-release of what the bump would give is released instead: 2.0.0-rc1 -> 2.0.0 with major.
+/// None if part isn't major, minor or patch, or if it's already 4294967295
+pub fn bump(version: &Version, part: &str) -> Option<Version> {
+  let released = version.pre_release.is_some() && version.extra_version.is_none();
+  let (major, minor, patch) = (version.major, version.minor, version.patch);
+  Some(match part {
+    "major" if released && minor == 0 && patch == 0 => Version::new(major, 0, 0),
+    "major" => Version::new(major.checked_add(1)?, 0, 0),
+    "minor" if released && patch == 0 => Version::new(major, minor, 0),
+    "minor" => Version::new(major, minor.checked_add(1)?, 0),
+    "patch" if released => Version::new(major, minor, patch),
+    "patch" => Version::new(major, minor, patch.checked_add(1)?),
+    _ => return None,
+  })
+}
+
+fn write_line<W: Write>(output: &mut W, line: impl std::fmt::Display) -> Result<(), String> {
+  writeln!(output, "{}", line).map_err(|e| e.to_string())
+}
+
+fn command<R: BufRead, W: Write>(name: &str, args: &Arguments, input: R, output: &mut W) -> Result<u8, String> {
+  match name {
+    "parse" => {
+      args.only(&[])?;
+      if args.positional.is_empty() {
+        return Err("usage: yasemver parse <version>...".to_string());
+      }
+      for text in &args.positional {
+        write_line(output, to_json(&version(text)?))?;
+      }
+      Ok(SUCCESS)
+    }
+    "compare" => {
+      args.only(&[])?;
+      let versions = args.exactly(2, "compare <a> <b>")?;
+      let ordering = SemVer.cmp(&version(&versions[0])?, &version(&versions[1])?);
+      write_line(output, ordering as i8)?;
+      Ok(SUCCESS)
+    }
+    "satisfies" => {
+      args.only(&[])?;
+      let Some((range_text, versions)) = args.positional.split_first().filter(|(_, versions)| !versions.is_empty()) else {
+        return Err("usage: yasemver satisfies <range> <version>...".to_string());
+      };
+      let range = range(range_text)?;
+      let mut all = true;
+      for text in versions {
+        match range.contains(version(text)?) {
+          true => write_line(output, text)?,
+          false => all = false,
+        }
+      }
+      Ok(if all { SUCCESS } else { NO })
+    }
+    "sort" => {
+      args.only(&["--reverse", "--unique"])?;
+      args.exactly(0, "sort [--reverse] [--unique] < versions")?;
+      let mut versions = read_versions(input)?;
+      // stable, so equal versions stay in the order they came in
+      versions.sort_by(|(_, a), (_, b)| SemVer.cmp(a, b).then_with(|| a.build.cmp(&b.build)));
+      if args.flag("--unique") {
+        versions.dedup_by(|(_, a), (_, b)| a.is(b));
+      }
+      if args.flag("--reverse") {
+        versions.reverse();
+      }
+      for (text, _) in versions {
+        write_line(output, text)?;
+      }
+      Ok(SUCCESS)
+    }
+    "max-satisfying" => {
+      args.only(&[])?;
+      let [range_text, versions @ ..] = args.positional.as_slice() else {
+        return Err("usage: yasemver max-satisfying <range> [version...]".to_string());
+      };
+      let range = range(range_text)?;
+      let versions = match versions {
+        [] => read_versions(input)?,
+        versions => versions.iter().map(|text| Ok((text.clone(), version(text)?))).collect::<Result<_, String>>()?,
+      };
+      let max = versions.into_iter()
+        .filter(|(_, v)| range.contains(v.clone()))
+        .max_by(|(_, a), (_, b)| SemVer.cmp(a, b));
+      match max {
+        Some((text, _)) => {
+          write_line(output, text)?;
+          Ok(SUCCESS)
+        }
+        None => Ok(NO),
+      }
+    }
+    "bump" => {
+      args.only(&[])?;
+      let arguments = args.exactly(2, "bump <major|minor|patch> <version>")?;
+      let (part, text) = (&arguments[0], &arguments[1]);
+      let bumped = bump(&version(text)?, part).ok_or_else(|| match part.as_str() {
+        "major" | "minor" | "patch" => format!("can't bump the {} of {}, it's already 4294967295", part, text),
+        _ => format!("can't bump {}, it's major, minor or patch", part),
+      })?;
+      write_line(output, bumped)?;
+      Ok(SUCCESS)
+    }
+    "diff" => {
+      args.only(&[])?;
+      let versions = args.exactly(2, "diff <a> <b>")?;
+      let (a, b) = (version(&versions[0])?, version(&versions[1])?);
+      let (older, newer) = match SemVer.cmp(&a, &b) {
+        Ordering::Greater => (b, a),
+        _ => (a, b),
+      };
+      match Change::between(&older, &newer) {
+        Some(change) => {
+          write_line(output, change)?;
+          Ok(SUCCESS)
+        }
+        None => Ok(NO),
+      }
+    }
+    "normalize-range" => {
+      args.only(&[])?;
+      let text = &args.exactly(1, "normalize-range <range>")?[0];
+      let (range, _) = Range::parse_lenient(text.as_str()).map_err(|e| format!("invalid range\n{}", diagnostics::render(text, &e)))?;
+      write_line(output, range)?;
+      Ok(SUCCESS)
+    }
+    "filter" => {
+      args.only(&["--prerelease", "--invalid"])?;
+      let range = range(&args.exactly(1, "filter <range> [--prerelease[=include|exclude|only]] [--invalid=skip|error|pass] < versions")?[0])?;
+      let prerelease = match args.value("--prerelease") {
+        None | Some("exclude") => Prerelease::Exclude,
+        Some("" | "include") => Prerelease::Include,
+        Some("only") => Prerelease::Only,
+        Some(other) => return Err(format!("--prerelease is include, exclude or only, not {}", other)),
+      };
+      let invalid = match args.value("--invalid") {
+        None | Some("skip") => Invalid::Skip,
+        Some("error") => Invalid::Error,
+        Some("pass") => Invalid::Pass,
+        Some(other) => return Err(format!("--invalid is skip, error or pass, not {}", other)),
+      };
+      filter(&range, prerelease, invalid, input, output)
+    }
+    "help" | "--help" | "-h" => {
+      write_line(output, HELP)?;
+      Ok(SUCCESS)
+    }
+    command => Err(format!("unknown command {}\n{}", command, HELP)),
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prerelease {
+  Include,
+  Exclude,
+  Only,
+}
+
+// lines that aren't versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Invalid {
+  Skip,
+  Error,
+  Pass,
+}
+
+// one line at a time, so it works on endless input and what's matched so far is already written when there's an error
+fn filter<R: BufRead, W: Write>(range: &Range, prerelease: Prerelease, invalid: Invalid, input: R, output: &mut W) -> Result<u8, String> {
+  let mut matched = false;
+  for line in lines(input) {
+    let (i, text) = line?;
+    // whether to write it, and whether it's a match
+    let (write, counts) = match Version::parse(text.as_str()) {
+      Ok(version) => {
+        let keep = range.contains(version.clone()) && match prerelease {
+          Prerelease::Include => true,
+          Prerelease::Exclude => version.pre_release.is_none(),
+          Prerelease::Only => version.pre_release.is_some(),
+        };
+        (keep, true)
+      }
+      Err(e) => match invalid {
+        Invalid::Skip => (false, false),
+        Invalid::Error => return Err(format!("line {}: invalid version\n{}", i, diagnostics::render(&text, &e))),
+        Invalid::Pass => (true, false),
+      },
+    };
+    if !write {
+      continue;
+    }
+    match writeln!(output, "{}", text) {
+      Ok(()) => matched |= counts,
+      // whoever reads it (head) has enough
+      Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(SUCCESS),
+      Err(e) => return Err(e.to_string()),
+    }
+  }
+  Ok(if matched { SUCCESS } else { NO })
+}
+
+/// Runs the yasemver command line (args without the program name) and returns its exit code.
+/// Errors go to errors, prefixed with yasemver:
+pub fn run<R: BufRead, W: Write, E: Write>(args: &[String], input: R, output: &mut W, errors: &mut E) -> u8 {
+  let Some((name, rest)) = args.split_first() else {
+    let _ = writeln!(errors, "{}", HELP);
+    return USAGE;
+  };
+  match command(name, &Arguments::new(rest), input, output) {
+    Ok(code) => code,
+    Err(message) => {
+      let _ = writeln!(errors, "yasemver: {}", message);
+      USAGE
+    }
+  }
+}
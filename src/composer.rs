@@ -0,0 +1,234 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::ParseError;
+use crate::implementations::{from_radix, Op, Range, RangeSet, Version};
+use crate::written::Written;
+
+// https://getcomposer.org/doc/articles/versions.md#stabilities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stability {
+  Dev,
+  Alpha,
+  Beta,
+  RC,
+  Stable,
+}
+
+impl Stability {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name.to_lowercase().as_str() {
+      "dev" => Some(Self::Dev),
+      "alpha" => Some(Self::Alpha),
+      "beta" => Some(Self::Beta),
+      "rc" => Some(Self::RC),
+      "stable" => Some(Self::Stable),
+      _ => None
+    }
+  }
+
+  /// Stability of a version, read from its pre_release like composer does
+  /// 1.0.0-dev and 1.0.0-beta1-dev -> dev, 1.0.0-b2 -> beta, 1.0.0 and 1.0.0-patch1 -> stable
+  pub fn of(version: &Version) -> Self {
+    match &version.pre_release {
+      None => Self::Stable,
+      Some(pre) => Self::from_pre(&from_radix(pre)),
+    }
+  }
+
+  fn from_pre(pre: &str) -> Self {
+    let pre = pre.to_lowercase();
+    let letters = pre.trim_end_matches(|c: char| c.is_ascii_digit());
+    if pre.ends_with("dev") {
+      Self::Dev
+    } else if letters.starts_with("alpha") || letters == "a" {
+      Self::Alpha
+    } else if letters.starts_with("beta") || letters == "b" {
+      Self::Beta
+    } else if letters.starts_with("rc") {
+      Self::RC
+    } else {
+      Self::Stable
+    }
+  }
+}
+
+impl Display for Stability {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::Dev => "dev",
+      Self::Alpha => "alpha",
+      Self::Beta => "beta",
+      Self::RC => "RC",
+      Self::Stable => "stable",
+    })
+  }
+}
+
+// dev-main, or dev-main as 1.0.x-dev
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+  pub name: String,
+  pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+  pub ranges: RangeSet,
+  // lowest stability allowed, from @flags or versions like 1.0.0-beta2 in the constraint
+  pub min_stability: Stability,
+  pub branches: Vec<Branch>,
+}
+
+impl Constraint {
+  pub fn parse<S: Into<String>>(constraint: S) -> Result<Self, ParseError> {
+    composer_parser::parse_constraint(&constraint.into())
+  }
+
+  /// In one of the ranges and at least as stable as min_stability
+  pub fn allows(&self, version: &Version) -> bool {
+    Stability::of(version) >= self.min_stability && self.ranges.contains(version.clone())
+  }
+
+  pub fn allows_branch(&self, name: &str) -> bool {
+    let name = name.strip_prefix("dev-").unwrap_or(name);
+    self.branches.iter().any(|b| b.name == name)
+  }
+}
+
+impl FromStr for Constraint {
+  type Err = ParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+/// Only the ranges of a composer constraint, ignoring stability and branches
+pub fn parse_range<S: Into<String>>(range: S) -> Result<RangeSet, ParseError> {
+  Ok(Constraint::parse(range)?.ranges)
+}
+
+fn stability(written: &Written) -> Option<Stability> {
+  written.pre.as_ref().map(|pre| Stability::from_pre(pre)).filter(|s| *s != Stability::Stable)
+}
+
+// composer's ~ lets the last written number go up: ~1.2 -> <2.0, ~1.2.3 -> <1.3.0, ~1 -> <2
+fn tilde(written: &Written) -> Result<Range, &'static str> {
+  let keep = written.numbers.len().saturating_sub(1).max(1);
+  written.between(Written::next(&written.numbers[..keep])?)
+}
+
+// 1.0.* -> >=1.0 <1.1
+fn wildcard(numbers: Vec<u32>) -> Result<Range, &'static str> {
+  let upper = Written::next(&numbers)?;
+  Written::new(numbers, None).between(upper)
+}
+
+// 1.0 - 2.0 -> >=1.0 <2.1, 1.0.0 - 2.0.0 -> >=1.0.0 <=2.0.0
+fn hyphen(from: &Written, to: &Written) -> Result<Range, &'static str> {
+  if to.numbers.len() < 3 {
+    from.between(Written::next(&to.numbers)?)
+  } else {
+    Ok(Range::from_ver_vec(vec![(Op::Ge, from.to_version()?), (Op::Le, to.to_version()?)]))
+  }
+}
+
+fn with_op(written: &Written, op: &str) -> Result<Range, &'static str> {
+  match op {
+    "~" => tilde(written),
+    "^" => written.caret(),
+    _ => Ok(Range::from_ver_vec(vec![(Op::from_str(op).unwrap(), written.to_version()?)])),
+  }
+}
+
+enum Atom {
+  Range(Box<Range>, Option<Stability>),
+  Branch(Branch),
+}
+
+fn build_constraint(groups: Vec<Vec<Atom>>) -> Constraint {
+  let mut ranges = vec![];
+  let mut branches = vec![];
+  let mut min_stability: Option<Stability> = None;
+  for group in groups {
+    // inside a group everything has to match
+    let mut range: Option<Range> = None;
+    for atom in group {
+      match atom {
+        Atom::Range(r, stability) => {
+          range = Some(match range {
+            Some(range) => range.intersect(&r),
+            None => *r,
+          });
+          if let Some(stability) = stability {
+            min_stability = Some(min_stability.map_or(stability, |s| s.min(stability)));
+          }
+        }
+        Atom::Branch(branch) => branches.push(branch),
+      }
+    }
+    if let Some(range) = range {
+      ranges.push(range);
+    }
+  }
+  Constraint {
+    ranges: RangeSet::new(ranges),
+    min_stability: min_stability.unwrap_or(Stability::Stable),
+    branches,
+  }
+}
+
+peg::parser!( grammar composer_parser() for str {
+  rule _() = [' ' | '\t']*
+  rule __() = [' ' | '\t']+
+
+  rule num() -> u32
+    = n:$(['0'..='9']+) {? n.parse().or(Err("number")) }
+
+  // beta2, -RC1, .alpha.1, -dev
+  rule pre() -> String
+    = ['-' | '.' | '_']? l:$(['a'..='z' | 'A'..='Z']+) n:(['.' | '-']? n:$(['0'..='9']+) { n })? {
+      format!("{}{}", l, n.unwrap_or(""))
+    }
+
+  rule flag() -> Stability
+    = "@" s:$(['a'..='z' | 'A'..='Z']+) {? Stability::from_name(s).ok_or("stability flag") }
+
+  rule version() -> Written
+    = ['v' | 'V']? n:(num() **<1,4> ".") p:pre()? { Written::new(n, p) }
+
+  rule wildcard_numbers() -> Vec<u32>
+    = ['v' | 'V']? n:(num() ++ ".") "." ['*' | 'x' | 'X'] { n }
+
+  rule branch() -> Branch
+    = "dev-" n:$([^ ' ' | '\t' | ',' | '|' | '@']+) a:(__ "as" __ a:$([^ ' ' | '\t' | ',' | '|']+) { a.to_string() })? {
+      Branch { name: n.to_string(), alias: a }
+    }
+
+  rule op() -> &'input str
+    = $("!=" / ">=" / "<=" / "==" / "=" / ">" / "<" / "~" / "^" / "")
+
+  rule atom() -> Atom
+    = b:branch() { Atom::Branch(b) }
+    / ['*' | 'x' | 'X'] !['0'..='9'] { Atom::Range(Box::default(), None) }
+    // 1.0.x-dev, the dev versions of a branch alias
+    / n:wildcard_numbers() "-dev" {? Ok(Atom::Range(Box::new(wildcard(n)?), Some(Stability::Dev))) }
+    / n:wildcard_numbers() {? Ok(Atom::Range(Box::new(wildcard(n)?), None)) }
+    / a:version() __ "-" __ b:version() {? Ok(Atom::Range(Box::new(hyphen(&a, &b)?), stability(&a).or(stability(&b)))) }
+    / o:op() _ v:version() {? Ok(Atom::Range(Box::new(with_op(&v, o)?), stability(&v))) }
+
+  rule flagged() -> Atom
+    = a:atom() f:flag()? {
+      match (a, f) {
+        (Atom::Range(r, s), Some(f)) => Atom::Range(r, Some(s.map_or(f, |s| s.min(f)))),
+        (a, _) => a,
+      }
+    }
+    / f:flag() { Atom::Range(Box::default(), Some(f)) }
+
+  rule group() -> Vec<Atom>
+    = a:(flagged() ++ ((_ "," _) / __)) { a }
+
+  pub rule parse_constraint() -> Constraint
+    = _ g:(group() ++ (_ ("||" / "|") _)) _ ![_] { build_constraint(g) }
+});
@@ -0,0 +1,358 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::implementations::{from_radix, Version};
+
+// [epoch:]upstream_version[-debian_revision]
+// https://www.debian.org/doc/debian-policy/ch-controlfields.html#version
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr))]
+pub struct DebianVersion {
+  pub epoch: u32,
+  pub upstream: String,
+  pub revision: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DebianError {
+  #[error("empty version string")]
+  Empty,
+  #[error("invalid epoch: {0}")]
+  InvalidEpoch(String),
+  #[error("empty upstream version")]
+  EmptyUpstream,
+  #[error("upstream version must start with a digit: {0}")]
+  UpstreamNotDigit(String),
+  #[error("invalid character {0:?} in {1}")]
+  InvalidChar(char, String),
+  #[error("empty debian revision")]
+  EmptyRevision,
+  #[error("{0} can't be converted without losing information")]
+  Lossy(String),
+  #[error("invalid relation: {0}")]
+  InvalidRelation(String),
+}
+
+impl DebianVersion {
+  pub fn new<S: Into<String>>(epoch: u32, upstream: S, revision: Option<S>) -> Self {
+    Self {
+      epoch,
+      upstream: upstream.into(),
+      revision: revision.map(|s| s.into()),
+    }
+  }
+
+  pub fn parse<S: Into<String>>(version: S) -> Result<Self, DebianError> {
+    let version = version.into();
+    let version = version.trim();
+    if version.is_empty() {
+      return Err(DebianError::Empty);
+    }
+    // epoch is everything before the first colon, revision everything after the last hyphen
+    let (epoch, rest) = match version.split_once(':') {
+      Some((e, rest)) => {
+        if e.is_empty() || !e.chars().all(|c| c.is_ascii_digit()) {
+          return Err(DebianError::InvalidEpoch(e.to_string()));
+        }
+        (e.parse().map_err(|_| DebianError::InvalidEpoch(e.to_string()))?, rest)
+      }
+      None => (0, version),
+    };
+    let (upstream, revision) = match rest.rsplit_once('-') {
+      Some((u, r)) => (u, Some(r)),
+      None => (rest, None),
+    };
+    if upstream.is_empty() {
+      return Err(DebianError::EmptyUpstream);
+    }
+    if !upstream.starts_with(|c: char| c.is_ascii_digit()) {
+      return Err(DebianError::UpstreamNotDigit(upstream.to_string()));
+    }
+    // hyphens are only allowed in upstream if there's a revision, colons if there's an epoch
+    for c in upstream.chars() {
+      let ok = c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '~')
+        || (c == '-' && revision.is_some())
+        || (c == ':' && version.contains(':'));
+      if !ok {
+        return Err(DebianError::InvalidChar(c, upstream.to_string()));
+      }
+    }
+    if let Some(r) = revision {
+      if r.is_empty() {
+        return Err(DebianError::EmptyRevision);
+      }
+      if let Some(c) = r.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '~'))) {
+        return Err(DebianError::InvalidChar(c, r.to_string()));
+      }
+    }
+    Ok(Self::new(epoch, upstream, revision))
+  }
+}
+
+// dpkg's order(): digits and the end of the string sort as 0, letters by their ascii value,
+// '~' before everything (even the end) and everything else after letters
+fn order(c: Option<u8>) -> i32 {
+  match c {
+    None => 0,
+    Some(c) if c.is_ascii_digit() => 0,
+    Some(c) if c.is_ascii_alphabetic() => c as i32,
+    Some(b'~') => -1,
+    Some(c) => c as i32 + 256,
+  }
+}
+
+/// Same algorithm as dpkg's verrevcmp, used for both the upstream version and the revision
+pub fn verrevcmp(a: &str, b: &str) -> Ordering {
+  let (a, b) = (a.as_bytes(), b.as_bytes());
+  let (mut i, mut j) = (0, 0);
+  while i < a.len() || j < b.len() {
+    // non digit part
+    while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+      let ac = order(a.get(i).copied());
+      let bc = order(b.get(j).copied());
+      if ac != bc {
+        return ac.cmp(&bc);
+      }
+      i += 1;
+      j += 1;
+    }
+    // digit part, leading zeros are ignored
+    while i < a.len() && a[i] == b'0' {
+      i += 1;
+    }
+    while j < b.len() && b[j] == b'0' {
+      j += 1;
+    }
+    let mut first_diff = Ordering::Equal;
+    while i < a.len() && a[i].is_ascii_digit() && j < b.len() && b[j].is_ascii_digit() {
+      if first_diff == Ordering::Equal {
+        first_diff = a[i].cmp(&b[j]);
+      }
+      i += 1;
+      j += 1;
+    }
+    if i < a.len() && a[i].is_ascii_digit() {
+      return Ordering::Greater;
+    }
+    if j < b.len() && b[j].is_ascii_digit() {
+      return Ordering::Less;
+    }
+    if first_diff != Ordering::Equal {
+      return first_diff;
+    }
+  }
+  Ordering::Equal
+}
+
+impl Ord for DebianVersion {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // a missing revision compares like an empty one, which is the same as "0"
+    self.epoch.cmp(&other.epoch)
+      .then_with(|| verrevcmp(&self.upstream, &other.upstream))
+      .then_with(|| verrevcmp(
+        self.revision.as_deref().unwrap_or(""),
+        other.revision.as_deref().unwrap_or(""),
+      ))
+  }
+}
+impl PartialOrd for DebianVersion {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl PartialEq for DebianVersion {
+  // equality is dpkg's equality, so 1.0 == 1.0-0 == 0:1.0
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Display for DebianVersion {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if self.epoch != 0 {
+      write!(f, "{}:", self.epoch)?;
+    }
+    write!(f, "{}", self.upstream)?;
+    if let Some(revision) = &self.revision {
+      write!(f, "-{}", revision)?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for DebianVersion {
+  type Err = DebianError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+// 1.2.3.4-rc1 <-> 1.2.3.4~rc1, since ~ is what debian uses for pre-releases
+// build can't be represented and epoch/revision can't be represented the other way around
+impl TryFrom<&Version> for DebianVersion {
+  type Error = DebianError;
+
+  fn try_from(version: &Version) -> Result<Self, Self::Error> {
+    if version.build.is_some() {
+      return Err(DebianError::Lossy(version.to_string()));
+    }
+    let mut upstream = format!("{}.{}.{}", version.major, version.minor, version.patch);
+    if let Some(extra) = &version.extra_version {
+      upstream.push_str(&format!(".{}", from_radix(extra)));
+    }
+    if let Some(pre) = &version.pre_release {
+      upstream.push_str(&format!("~{}", from_radix(pre)));
+    }
+    Ok(Self::new(0, upstream, None))
+  }
+}
+impl TryFrom<&DebianVersion> for Version {
+  type Error = DebianError;
+
+  fn try_from(version: &DebianVersion) -> Result<Self, Self::Error> {
+    let lossy = || DebianError::Lossy(version.to_string());
+    if version.epoch != 0 || version.revision.is_some() || version.upstream.matches('~').count() > 1 {
+      return Err(lossy());
+    }
+    // the pre-release has to be a single base36 number, see Version::new_w_extra
+    if let Some((_, pre)) = version.upstream.split_once('~') {
+      if pre.is_empty() || !pre.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(lossy());
+      }
+    }
+    let converted = Version::parse(version.upstream.replace('~', "-")).map_err(|_| lossy())?;
+    // only accept it if it comes back exactly the same, 1.2 would come back as 1.2.0 which dpkg sorts differently
+    match DebianVersion::try_from(&converted) {
+      Ok(back) if back.to_string() == version.to_string() => Ok(converted),
+      _ => Err(lossy()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelationOp {
+  Lt, // <<
+  Le, // <= (and the deprecated <)
+  Eq, // =
+  Ge, // >= (and the deprecated >)
+  Gt, // >>
+}
+
+impl RelationOp {
+  pub fn from_symbol(op: &str) -> Option<Self> {
+    match op {
+      "<<" => Some(Self::Lt),
+      "<=" | "<" => Some(Self::Le),
+      "=" => Some(Self::Eq),
+      ">=" | ">" => Some(Self::Ge),
+      ">>" => Some(Self::Gt),
+      _ => None
+    }
+  }
+}
+
+impl Display for RelationOp {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::Lt => "<<",
+      Self::Le => "<=",
+      Self::Eq => "=",
+      Self::Ge => ">=",
+      Self::Gt => ">>",
+    })
+  }
+}
+
+// a single package relation, like "libc6 (>= 2.34)" or "foo:any"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+  pub name: String,
+  pub arch: Option<String>,
+  pub constraint: Option<(RelationOp, DebianVersion)>,
+}
+
+impl Relation {
+  pub fn parse<S: Into<String>>(relation: S) -> Result<Self, DebianError> {
+    let relation = relation.into();
+    deb_parser::relation(&relation).map_err(|_| DebianError::InvalidRelation(relation.clone()))?
+  }
+
+  pub fn satisfied_by(&self, version: &DebianVersion) -> bool {
+    match &self.constraint {
+      None => true,
+      Some((op, wanted)) => {
+        let ord = version.cmp(wanted);
+        match op {
+          RelationOp::Lt => ord == Ordering::Less,
+          RelationOp::Le => ord != Ordering::Greater,
+          RelationOp::Eq => ord == Ordering::Equal,
+          RelationOp::Ge => ord != Ordering::Less,
+          RelationOp::Gt => ord == Ordering::Greater,
+        }
+      }
+    }
+  }
+}
+
+impl Display for Relation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name)?;
+    if let Some(arch) = &self.arch {
+      write!(f, ":{}", arch)?;
+    }
+    if let Some((op, version)) = &self.constraint {
+      write!(f, " ({} {})", op, version)?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Relation {
+  type Err = DebianError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+/// Parses a whole Depends-like field: "a (>= 1), b | c (<< 2)"
+/// The outer vec is AND, the inner vec is OR (alternatives)
+pub fn parse_relations<S: Into<String>>(field: S) -> Result<Vec<Vec<Relation>>, DebianError> {
+  let field = field.into();
+  deb_parser::relations(&field).map_err(|_| DebianError::InvalidRelation(field.clone()))?
+    .into_iter()
+    .map(|alternatives| alternatives.into_iter().collect::<Result<Vec<_>, _>>())
+    .collect()
+}
+
+peg::parser!( grammar deb_parser() for str {
+  rule _() = [' ' | '\t' | '\n']*
+
+  rule name() -> String
+    = n:$(['a'..='z' | '0'..='9'] ['a'..='z' | '0'..='9' | '+' | '-' | '.']*) { n.to_string() }
+
+  rule arch() -> String
+    = ":" a:$(['a'..='z' | '0'..='9' | '-']+) { a.to_string() }
+
+  rule op() -> RelationOp
+    = o:$("<<" / "<=" / ">=" / ">>" / "=" / "<" / ">") { RelationOp::from_symbol(o).unwrap() }
+
+  rule version() -> &'input str
+    = $([^ ')' | ' ' | '\t' | '\n']+)
+
+  rule constraint() -> Result<(RelationOp, DebianVersion), DebianError>
+    = "(" _ o:op() _ v:version() _ ")" { Ok((o, DebianVersion::parse(v)?)) }
+
+  rule single() -> Result<Relation, DebianError>
+    = n:name() a:arch()? _ c:constraint()? _ {
+      Ok(Relation { name: n, arch: a, constraint: c.transpose()? })
+    }
+
+  pub rule relation() -> Result<Relation, DebianError>
+    = _ r:single() ![_] { r }
+
+  pub rule relations() -> Vec<Vec<Result<Relation, DebianError>>>
+    = _ r:((_ a:(single() ++ ("|" _)) { a }) ** ",") _ ![_] { r }
+});
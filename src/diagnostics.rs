@@ -0,0 +1,323 @@
+use std::fmt::{Display, Formatter};
+use peg::Parse;
+use peg::str::LineCol;
+use crate::ParseError;
+use crate::lossless::{Component, LosslessVersion, Span};
+use crate::rubygems::GemVersion;
+
+// something the lenient parser accepted but changed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+  LeadingV,
+  Whitespace,
+  // 1.2 -> 1.2.0, the span is empty and where it would be
+  MissingComponent(Component),
+  LeadingZeros(Component),
+  // 1.0+win-rc -> 1.0.0-rc+win
+  BuildBeforePre,
+  UppercaseFolded(Component),
+  // dots and hyphens in the extra version and pre-release can't be stored, rc.1 -> rc1
+  SeparatorsDropped(Component),
+  // 1. or 1.2.3, (a separator the parser skips)
+  TrailingSeparator,
+  // =>1.2 -> >=1.2, ~>2.1 -> >=2.1 <3, only with Range::parse_lenient
+  OperatorRespelled { written: String, meant: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+  pub kind: WarningKind,
+  pub span: Span,
+}
+
+impl Display for WarningKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::LeadingV => write!(f, "the leading v was ignored"),
+      Self::Whitespace => write!(f, "whitespace was ignored"),
+      Self::MissingComponent(c) => write!(f, "the {} is missing, 0 was used", c),
+      Self::LeadingZeros(c) => write!(f, "the leading zeros of the {} were ignored", c),
+      Self::BuildBeforePre => write!(f, "the build was written before the pre-release, they were swapped"),
+      Self::UppercaseFolded(c) => write!(f, "the {} was lowercased", c),
+      Self::SeparatorsDropped(c) => write!(f, "the dots and hyphens of the {} were dropped", c),
+      Self::TrailingSeparator => write!(f, "the trailing separator was ignored"),
+      Self::OperatorRespelled { written, meant } => write!(f, "`{}` was read as `{}`", written, meant),
+    }
+  }
+}
+
+impl Display for Warning {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} (at {}..{})", self.kind, self.span.start, self.span.end)
+  }
+}
+
+fn warning(kind: WarningKind, span: Span) -> Warning {
+  Warning { kind, span }
+}
+
+/// Everything the lenient parser normalized in version, in the order it appears
+pub fn report(version: &LosslessVersion) -> Vec<Warning> {
+  let text = version.as_str();
+  let spans = &version.spans;
+  let mut warnings = vec![];
+
+  // " v 1" -> whitespace, v, whitespace
+  let prefix = &text[..spans.major.start];
+  let mut at = 0;
+  for (i, c) in prefix.char_indices() {
+    if c == 'v' || c == 'V' {
+      if i > at {
+        warnings.push(warning(WarningKind::Whitespace, at..i));
+      }
+      warnings.push(warning(WarningKind::LeadingV, i..i + 1));
+      at = i + 1;
+    }
+  }
+  if prefix.len() > at {
+    warnings.push(warning(WarningKind::Whitespace, at..prefix.len()));
+  }
+
+  let mut end = spans.major.end;
+  for (component, span) in [(Component::Major, Some(&spans.major)), (Component::Minor, spans.minor.as_ref()), (Component::Patch, spans.patch.as_ref())] {
+    match span {
+      Some(span) => {
+        let number = &text[span.clone()];
+        if number.len() > 1 && number.starts_with('0') {
+          warnings.push(warning(WarningKind::LeadingZeros(component), span.clone()));
+        }
+        end = span.end;
+      }
+      None => warnings.push(warning(WarningKind::MissingComponent(component), end..end)),
+    }
+  }
+
+  let build_first = matches!((&spans.build, &spans.pre), (Some(b), Some(p)) if b.start < p.start);
+  if let (true, Some(build), Some(pre)) = (build_first, &spans.build, &spans.pre) {
+    warnings.push(warning(WarningKind::BuildBeforePre, build.start - 1..pre.end));
+  }
+  let mut parts = [(Component::Extra, &spans.extra), (Component::Pre, &spans.pre), (Component::Build, &spans.build)];
+  parts.sort_by_key(|(_, span)| span.as_ref().map(|s| s.start));
+  for (component, span) in parts {
+    let Some(span) = span else { continue };
+    let part = &text[span.clone()];
+    if part.chars().any(|c| c.is_ascii_uppercase()) {
+      warnings.push(warning(WarningKind::UppercaseFolded(component), span.clone()));
+    }
+    if component != Component::Build && part.contains(['.', '-', '_']) {
+      warnings.push(warning(WarningKind::SeparatorsDropped(component), span.clone()));
+    }
+    end = end.max(span.end);
+  }
+
+  // what's left is a skipped separator and/or whitespace: "1.", "1.2.3, "
+  let rest = &text[end..];
+  let trimmed = rest.trim_end_matches(' ');
+  if !trimmed.is_empty() {
+    warnings.push(warning(WarningKind::TrailingSeparator, end..end + trimmed.len()));
+  }
+  if trimmed.len() < rest.len() {
+    warnings.push(warning(WarningKind::Whitespace, end + trimmed.len()..text.len()));
+  }
+  warnings
+}
+
+/// A replacement that would probably fix the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+  pub span: Span,
+  // None when it's advice rather than an edit
+  pub replacement: Option<String>,
+  pub message: String,
+}
+
+/// A ParseError explained for people: the input with a caret under where it failed,
+/// what was wrong in plain words and what to do about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub input: String,
+  // the character that couldn't be parsed, empty if the input ended too early
+  pub span: Span,
+  pub explanation: String,
+  pub suggestions: Vec<Suggestion>,
+}
+
+// (written, meant, why) things people write that the grammar doesn't take
+pub(crate) const MISTAKES: &[(&str, &str, &str)] = &[
+  ("=>", ">=", "`=>` isn't an operator"),
+  ("=<", "<=", "`=<` isn't an operator"),
+  ("≥", ">=", "only ASCII operators are supported"),
+  ("≤", "<=", "only ASCII operators are supported"),
+  ("~>", "~", "`~>` is RubyGems' pessimistic operator (rubygems::parse_range reads those)"),
+  ("~=", "~", "`~=` is PEP 440's compatible release operator (pep508::parse_specifiers reads those)"),
+  ("&&", "", "requirements are joined with spaces"),
+];
+
+// ~> and ~= are >= and < the version with its second to last number bumped, ~>2.1 -> >=2.1 <3
+fn pessimistic(version: &str) -> Option<String> {
+  let bumped = GemVersion::parse(version.trim_start_matches(['v', 'V'])).ok()?.bump();
+  Some(format!(">={} <{}", version, bumped))
+}
+
+/// Rewrites the operators in MISTAKES that have a meaning (=>, =<, ≥, ≤, ~> and ~=) to what they mean,
+/// with a warning for each. The spans are in range, not in the rewritten text.
+/// The last value is each rewrite's span in the rewritten text and in range, for original_location.
+pub(crate) fn respell_operators(range: &str) -> (String, Vec<Warning>, Vec<(Span, Span)>) {
+  let mut respelled = String::new();
+  let mut warnings = vec![];
+  let mut rewrites = vec![];
+  let mut at = 0;
+  while at < range.len() {
+    let rest = &range[at..];
+    let Some(written) = ["=>", "=<", "≥", "≤", "~>", "~="].into_iter().find(|w| rest.starts_with(w)) else {
+      let c = rest.chars().next().unwrap();
+      respelled.push(c);
+      at += c.len_utf8();
+      continue;
+    };
+    let span = at..at + written.len();
+    at = span.end;
+    let meant = match written {
+      "=>" | "≥" => ">=".to_string(),
+      "=<" | "≤" => "<=".to_string(),
+      _ => {
+        // the version is read too, until the next space or comma
+        let after = &range[at..];
+        let spaces = after.len() - after.trim_start_matches(' ').len();
+        let version_len = after[spaces..].find([' ', ',']).unwrap_or(after.len() - spaces);
+        match pessimistic(&after[spaces..spaces + version_len]) {
+          Some(meant) => {
+            at += spaces + version_len;
+            meant
+          }
+          None => "~".to_string(),
+        }
+      }
+    };
+    rewrites.push((respelled.len()..respelled.len() + meant.len(), span.start..at));
+    respelled.push_str(&meant);
+    warnings.push(warning(WarningKind::OperatorRespelled { written: written.to_string(), meant }, span));
+  }
+  (respelled, warnings, rewrites)
+}
+
+/// Where offset in the text respell_operators rewrote is in range, the start of the rewrite if it's inside one
+pub(crate) fn original_location(range: &str, rewrites: &[(Span, Span)], offset: usize) -> LineCol {
+  let mut original = offset;
+  for (new, old) in rewrites {
+    if offset >= new.end {
+      original = offset - new.end + old.end;
+    } else if offset >= new.start {
+      original = old.start;
+    }
+  }
+  range.position_repr(original.min(range.len()))
+}
+
+// what peg says it expected, in words
+fn expected_name(token: &str) -> String {
+  match token {
+    "EOF" => "the end".to_string(),
+    "\" \"" => "a space".to_string(),
+    "number" | "['0'..='9']" => "a number".to_string(),
+    t if t.starts_with("['v'") => "v".to_string(),
+    t if t.starts_with('[') => "a letter or number".to_string(),
+    t if t.starts_with('"') => format!("`{}`", t.trim_matches('"')),
+    t => t.to_string(),
+  }
+}
+
+fn list(names: &[String]) -> String {
+  match names {
+    [] => String::new(),
+    [one] => one.clone(),
+    [rest @ .., last] => format!("{} or {}", rest.join(", "), last),
+  }
+}
+
+impl Diagnostic {
+  pub fn from_error(input: &str, error: &ParseError) -> Self {
+    let offset = error.location.offset.min(input.len());
+    let found = input[offset..].chars().next();
+    let span = offset..offset + found.map_or(0, |c| c.len_utf8());
+
+    let mut expected: Vec<String> = vec![];
+    for name in error.expected.tokens().map(expected_name) {
+      if !expected.contains(&name) {
+        expected.push(name);
+      }
+    }
+    let explanation = match found {
+      _ if input.trim().is_empty() => "the input is empty".to_string(),
+      None => format!("the input ended too early, expected {}", list(&expected)),
+      Some(c) if expected == ["the end"] => format!("unexpected `{}` after what was already read", c),
+      Some(c) => format!("unexpected `{}`, expected {}", c, list(&expected)),
+    };
+
+    let mut suggestions = vec![];
+    for (written, meant, why) in MISTAKES {
+      for (at, _) in input.match_indices(written) {
+        suggestions.push(Suggestion {
+          span: at..at + written.len(),
+          replacement: Some(meant.to_string()),
+          message: match meant.trim() {
+            "" => format!("remove `{}`, {}", written, why),
+            meant => format!("did you mean `{}`? {}", meant, why),
+          },
+        });
+      }
+    }
+    if input.contains("||") {
+      suggestions.push(Suggestion {
+        span: span.clone(),
+        replacement: None,
+        message: "a Range can't have alternatives, parse each side of `||` on its own (or use composer::Constraint)".to_string(),
+      });
+    }
+    let wildcard = input[offset..].starts_with(['x', 'X', '*']) && input[..offset].ends_with('.');
+    if wildcard {
+      suggestions.push(Suggestion {
+        span: offset - 1..span.end,
+        replacement: None,
+        message: "wildcards aren't supported, 1.x is ^1 and 1.2.x is ~1.2".to_string(),
+      });
+    }
+    suggestions.sort_by_key(|s| s.span.start);
+    Self { input: input.to_string(), span, explanation, suggestions }
+  }
+
+  /// The input with every suggested replacement applied, None if there's nothing to apply
+  pub fn fixed(&self) -> Option<String> {
+    let mut fixed = self.input.clone();
+    let mut replaced = false;
+    for s in self.suggestions.iter().rev() {
+      let Some(replacement) = &s.replacement else { continue };
+      fixed.replace_range(s.span.clone(), replacement);
+      replaced = true;
+    }
+    replaced.then_some(fixed)
+  }
+}
+
+impl Display for Diagnostic {
+  //  >=1.2 || 2
+  //        ^ unexpected `|`, expected ...
+  //  help: ...
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    // only the line that failed, and the caret counted in characters, not bytes
+    let line_start = self.input[..self.span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = self.input[self.span.start..].find('\n').map_or(self.input.len(), |i| self.span.start + i);
+    let column = self.input[line_start..self.span.start].chars().count();
+    writeln!(f, "{}", &self.input[line_start..line_end])?;
+    write!(f, "{}^ {}", " ".repeat(column), self.explanation)?;
+    for suggestion in &self.suggestions {
+      write!(f, "\nhelp: {}", suggestion.message)?;
+    }
+    Ok(())
+  }
+}
+
+/// Diagnostic::from_error(input, error).to_string()
+pub fn render(input: &str, error: &ParseError) -> String {
+  Diagnostic::from_error(input, error).to_string()
+}
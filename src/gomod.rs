@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::ParseError;
+
+// A go module version, always canonical: v1.2.3, v1.2.3-pre.1, v2.0.0+incompatible
+// https://go.dev/ref/mod#versions
+// Unlike Version this orders pre-releases like semver does, which is what go needs for pseudo-versions
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr))]
+pub struct ModVersion {
+  pub major: u64,
+  pub minor: u64,
+  pub patch: u64,
+  pub pre: Vec<String>,
+  pub build: Option<String>,
+}
+
+// v0.0.0-20191109021931-daa7c04131f5
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PseudoVersion {
+  // the tag the commit is based on, v1.2.3 for v1.2.4-0.2019..., None for v0.0.0-2019...
+  pub base: Option<ModVersion>,
+  pub timestamp: Timestamp,
+  pub revision: String,
+}
+
+// UTC, yyyymmddhhmmss
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+  pub year: u16,
+  pub month: u8,
+  pub day: u8,
+  pub hour: u8,
+  pub minute: u8,
+  pub second: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GoModError {
+  #[error("{path}@{version}: {reason}")]
+  InvalidVersion { path: String, version: String, reason: String },
+}
+
+impl ModVersion {
+  pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+    Self { major, minor, patch, pre: vec![], build: None }
+  }
+
+  pub fn parse<S: Into<String>>(version: S) -> Result<Self, ParseError> {
+    gomod_parser::parse_version(&version.into())
+  }
+
+  pub fn is_prerelease(&self) -> bool {
+    !self.pre.is_empty()
+  }
+
+  pub fn is_incompatible(&self) -> bool {
+    self.build.as_deref() == Some("incompatible")
+  }
+
+  pub fn is_pseudo(&self) -> bool {
+    self.pseudo().is_some()
+  }
+
+  /// Splits a pseudo-version into its base, timestamp and revision, None if it isn't one.
+  /// The three forms are vX.0.0-yyyymmddhhmmss-abcdef123456, vX.Y.Z-pre.0.yyyymmddhhmmss-abcdef123456
+  /// and vX.Y.(Z+1)-0.yyyymmddhhmmss-abcdef123456
+  pub fn pseudo(&self) -> Option<PseudoVersion> {
+    let (last, rest) = self.pre.split_last()?;
+    let (timestamp, revision) = last.split_once('-')?;
+    if revision.is_empty() || !revision.chars().all(|c| c.is_ascii_alphanumeric()) {
+      return None;
+    }
+    let timestamp = Timestamp::parse(timestamp)?;
+    let base = match rest {
+      // vX.0.0-yyyymmddhhmmss-abcdef123456
+      [] if self.minor == 0 && self.patch == 0 => None,
+      // vX.Y.(Z+1)-0.yyyymmddhhmmss-abcdef123456
+      [zero] if zero == "0" && self.patch > 0 => Some(Self::new(self.major, self.minor, self.patch - 1)),
+      // vX.Y.Z-pre.0.yyyymmddhhmmss-abcdef123456
+      [pre @ .., zero] if zero == "0" && !pre.is_empty() => Some(Self {
+        pre: pre.to_vec(),
+        ..Self::new(self.major, self.minor, self.patch)
+      }),
+      _ => return None,
+    };
+    Some(PseudoVersion { base, timestamp, revision: revision.to_string() })
+  }
+
+  /// Checks go's major version suffix rule for this version of the module at path:
+  /// example.com/m/v2 needs v2.x.x, example.com/m needs v0 or v1 (or +incompatible for v2 and up),
+  /// gopkg.in/yaml.v3 needs v3.x.x
+  pub fn check_path(&self, path: &str) -> Result<(), GoModError> {
+    let error = |reason: String| Err(GoModError::InvalidVersion {
+      path: path.to_string(),
+      version: self.to_string(),
+      reason,
+    });
+    match path_major(path) {
+      Some(major) => {
+        if self.is_incompatible() {
+          return error(format!("+incompatible can't be used with a /v{} module path", major));
+        }
+        if self.major != major {
+          return error(format!("should be v{}, not v{}", major, self.major));
+        }
+      }
+      None => {
+        if self.major >= 2 && !self.is_incompatible() {
+          return error(format!("should be v0 or v1, not v{} (or the path should end in /v{})", self.major, self.major));
+        }
+        if self.major < 2 && self.is_incompatible() {
+          return error("+incompatible is only for v2 and up".to_string());
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+// major version from the path suffix, /v2 or gopkg.in's .v2
+fn path_major(path: &str) -> Option<u64> {
+  let gopkg = path.starts_with("gopkg.in/");
+  let (_, suffix) = if gopkg { path.rsplit_once(".v")? } else { path.rsplit_once("/v")? };
+  if suffix.is_empty() || suffix.starts_with('0') || !suffix.chars().all(|c| c.is_ascii_digit()) {
+    return None;
+  }
+  let major = suffix.parse().ok()?;
+  // /v1 isn't a major suffix, gopkg.in/x.v1 is
+  if major < 2 && !gopkg {
+    return None;
+  }
+  Some(major)
+}
+
+impl Timestamp {
+  fn parse(s: &str) -> Option<Self> {
+    if s.len() != 14 || !s.chars().all(|c| c.is_ascii_digit()) {
+      return None;
+    }
+    let part = |from: usize, to: usize| s[from..to].parse::<u16>().ok();
+    let timestamp = Self {
+      year: part(0, 4)?,
+      month: part(4, 6)? as u8,
+      day: part(6, 8)? as u8,
+      hour: part(8, 10)? as u8,
+      minute: part(10, 12)? as u8,
+      second: part(12, 14)? as u8,
+    };
+    let valid = (1..=12).contains(&timestamp.month) && (1..=31).contains(&timestamp.day)
+      && timestamp.hour < 24 && timestamp.minute < 60 && timestamp.second < 60;
+    valid.then_some(timestamp)
+  }
+}
+
+impl Display for Timestamp {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:04}{:02}{:02}{:02}{:02}{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second)
+  }
+}
+
+// semver precedence, numeric identifiers are compared as numbers and are older than alphanumeric ones
+fn cmp_identifier(a: &str, b: &str) -> Ordering {
+  let numeric = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+  match (numeric(a), numeric(b)) {
+    (true, true) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+    (true, false) => Ordering::Less,
+    (false, true) => Ordering::Greater,
+    (false, false) => a.cmp(b),
+  }
+}
+
+impl Ord for ModVersion {
+  // build (and so +incompatible) is ignored
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.major.cmp(&other.major)
+      .then(self.minor.cmp(&other.minor))
+      .then(self.patch.cmp(&other.patch))
+      .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => self.pre.iter().zip(&other.pre)
+          .map(|(a, b)| cmp_identifier(a, b))
+          .find(|o| *o != Ordering::Equal)
+          .unwrap_or_else(|| self.pre.len().cmp(&other.pre.len())),
+      })
+  }
+}
+impl PartialOrd for ModVersion {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl PartialEq for ModVersion {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Display for ModVersion {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)?;
+    if !self.pre.is_empty() {
+      write!(f, "-{}", self.pre.join("."))?;
+    }
+    if let Some(build) = &self.build {
+      write!(f, "+{}", build)?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for ModVersion {
+  type Err = ParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+// a require line, example.com/m v1.2.3
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModRequirement {
+  pub path: String,
+  pub version: ModVersion,
+}
+
+impl ModRequirement {
+  pub fn new<S: Into<String>>(path: S, version: ModVersion) -> Self {
+    Self { path: path.into(), version }
+  }
+}
+
+/// Where minimal_version_selection gets the requirements of each module version from (usually its go.mod)
+pub trait ModRequirements {
+  fn requirements(&self, path: &str, version: &ModVersion) -> Vec<ModRequirement>;
+}
+
+/// Go's minimal version selection: walks the requirement graph from the root requirements
+/// and picks, for each module, the highest version required by anything reachable.
+/// Every module version is only visited once, so cycles are fine.
+pub fn minimal_version_selection<R: ModRequirements>(root: &[ModRequirement], provider: &R) -> BTreeMap<String, ModVersion> {
+  let mut selected: BTreeMap<String, ModVersion> = BTreeMap::new();
+  let mut visited: HashSet<(String, String)> = HashSet::new();
+  let mut queue: VecDeque<ModRequirement> = root.iter().cloned().collect();
+  while let Some(requirement) = queue.pop_front() {
+    if !visited.insert((requirement.path.clone(), requirement.version.to_string())) {
+      continue;
+    }
+    match selected.get(&requirement.path) {
+      Some(current) if *current >= requirement.version => {}
+      _ => {
+        selected.insert(requirement.path.clone(), requirement.version.clone());
+      }
+    }
+    queue.extend(provider.requirements(&requirement.path, &requirement.version));
+  }
+  selected
+}
+
+peg::parser!( grammar gomod_parser() for str {
+  rule num() -> u64
+    = n:$("0" / ['1'..='9'] ['0'..='9']*) {? n.parse().or(Err("number")) }
+
+  rule identifier() -> String
+    = i:$(['0'..='9' | 'a'..='z' | 'A'..='Z' | '-']+) {?
+      // numeric identifiers can't have leading zeros
+      if i.len() > 1 && i.starts_with('0') && i.chars().all(|c| c.is_ascii_digit()) { Err("identifier") } else { Ok(i.to_string()) }
+    }
+
+  rule build() -> String
+    = b:$((['0'..='9' | 'a'..='z' | 'A'..='Z' | '-']+) ++ ".") { b.to_string() }
+
+  pub rule parse_version() -> ModVersion
+    = "v" M:num() "." m:num() "." p:num() pre:("-" i:(identifier() ++ ".") { i })? b:("+" b:build() { b })? ![_] {
+      ModVersion { major: M, minor: m, patch: p, pre: pre.unwrap_or_default(), build: b }
+    }
+});
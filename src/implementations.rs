@@ -5,11 +5,23 @@ use std::str::FromStr;
 use radix::{RadixErr, RadixNum};
 use thiserror::Error;
 use crate::ParseError;
-use crate::Parser::{parse_range, parse_version};
+use crate::strict::{self, StrictError};
+use crate::lossless::{LosslessVersion, Span};
+use crate::diagnostics::{self, Warning};
+use crate::Parser::{find_version, parse_comparators, parse_range, parse_version};
 
 fn to_radix(s: &str) -> Result<RadixNum, RadixErr> {
+  // RadixNum adds the digits up in a usize, which overflows past 12 base 36 digits (a panic in debug builds
+  // and a wrong number in release). It has no error for that, IllegalDigit is the closest
+  if s.trim().len() > 12 {
+    return Err(RadixErr::IllegalDigit(s.trim().len()));
+  }
   RadixNum::from_str(s, 36)
 }
+// back to the (lowercase) text it was read from, minus leading zeros
+pub(crate) fn from_radix(r: &RadixNum) -> String {
+  r.as_str().to_lowercase()
+}
 
 
 
@@ -23,7 +35,7 @@ pub struct Version {
   pub pre_release: Option<RadixNum>,
   pub build: Option<String>,
 }
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr))]
 pub struct Range {
   pub min: Option<Version>, //inclusive
@@ -31,6 +43,11 @@ pub struct Range {
   pub except: Vec<Version>,
   pub include: Vec<Version>
 }
+// ranges joined with ||, a version only has to be in one of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeSet {
+  pub ranges: Vec<Range>
+}
 
 impl Default for Range {
   fn default() -> Self {
@@ -43,6 +60,58 @@ impl Version {
     let version: Self = parse_version(&version.into())?;
     Ok(version)
   }
+  /// Like parse, but keeps the text as it was written (see LosslessVersion)
+  pub fn parse_lossless<S:Into<String>>(version: S) -> Result<LosslessVersion, ParseError> {
+    LosslessVersion::parse(version)
+  }
+  /// Parses leniently like parse, and says what was normalized on the way:
+  /// "V 1." -> 1.0.0 with a leading v, whitespace, missing minor and patch and a trailing separator.
+  /// Fails exactly where parse does, the warnings are all it adds
+  pub fn parse_with_report<S:Into<String>>(version: S) -> Result<(Self, Vec<Warning>), ParseError> {
+    let text = version.into();
+    let version = Self::parse(text.as_str())?;
+    // LosslessVersion takes more than parse (1.0-rc.1), it's only read for where things were written
+    let warnings = LosslessVersion::parse(text).map(|l| diagnostics::report(&l)).unwrap_or_default();
+    Ok((version, warnings))
+  }
+  /// Every version in some text with where it is, "MyTool version 3.4.1-beta (build 77)" -> [3.4.1-beta at 15..25].
+  /// To not find every number, versions need a dot or a v (3.4, v3) and can't be part of a word.
+  /// Versions a Version can't store (a pre-release past 12 letters and digits) are skipped, and
+  /// four numbers are a version with an extra version, so an IP like 192.168.1.1 is found too.
+  pub fn find_all(text: &str) -> Vec<(Self, Span)> {
+    let mut found = vec![];
+    let mut at = 0;
+    while let Some((version, span)) = Self::find_from(text, at) {
+      at = span.end;
+      found.push((version, span));
+    }
+    found
+  }
+  pub fn extract_first(text: &str) -> Option<(Self, Span)> {
+    Self::find_from(text, 0)
+  }
+  fn find_from(text: &str, from: usize) -> Option<(Self, Span)> {
+    let mut at = from;
+    while at < text.len() {
+      let rest = &text[at..];
+      let c = rest.chars().next().unwrap();
+      let boundary = !text[..at].ends_with(|p: char| p.is_ascii_alphanumeric() || p == '.' || p == '-' || p == '+');
+      if boundary && (c.is_ascii_digit() || c == 'v' || c == 'V') {
+        // versions can't have spaces, so only what's before the next one is parsed
+        let word = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+        if let Ok((version, end)) = find_version(word) {
+          return Some((version, at..at + end));
+        }
+      }
+      at += c.len_utf8();
+    }
+    None
+  }
+  /// Only accepts valid SemVer 2.0.0 (no v, leading zeros, missing components, uppercase folding...),
+  /// the error says which rule was broken and where
+  pub fn parse_strict<S:Into<String>>(version: S) -> Result<Self, StrictError> {
+    strict::parse(&version.into())
+  }
   // https://play.rust-lang.org/?version=stable&mode=debug&edition=2021&gist=d78be90c82a7b80c949f30b5befcd6c2
   pub fn new_w_extra<S:Into<String>>(
     major: u32,
@@ -261,6 +330,10 @@ impl Range {
     if self.include.contains(&version) {
       return true;
     }
+    // =1.2.3 only has include, and it shouldn't contain everything else
+    if self.min.is_none() && self.max.is_none() && !self.include.is_empty() {
+      return false;
+    }
     let min = self.min.clone();
     let max = self.max.clone();
     // then check min and max
@@ -270,6 +343,51 @@ impl Range {
     // Next should be the oneliner but I'm not sure it works correctly so I'm keeping what's above
     // (self.min.is_none() || version >= self.min.unwrap()) && (self.max.is_none() || version < self.max.unwrap()) && !self.except.contains(&version) && self.include.contains(&version)
   }
+  /// Range that contains nothing, min == max
+  pub fn none() -> Self {
+    Self {
+      min: Some(Version::new(0, 0, 0)),
+      max: Some(Version::new(0, 0, 0)),
+      except: vec![],
+      include: vec![],
+    }
+  }
+  pub fn is_empty(&self) -> bool { // contains nothing
+    self.include.is_empty() && matches!((&self.min, &self.max), (Some(min), Some(max)) if min >= max)
+  }
+  /// Range with the versions contained in both, assuming both are valid
+  pub fn intersect(&self, other: &Range) -> Range {
+    let only_include = |r: &Range| r.min.is_none() && r.max.is_none() && !r.include.is_empty();
+    let mut include: Vec<Version> = vec![];
+    for version in self.include.iter().filter(|v| other.contains((*v).clone()))
+      .chain(other.include.iter().filter(|v| self.contains((*v).clone()))) {
+      if !include.contains(version) {
+        include.push(version.clone());
+      }
+    }
+    let mut except = self.except.clone();
+    for version in &other.except {
+      if !except.contains(version) {
+        except.push(version.clone());
+      }
+    }
+    if only_include(self) || only_include(other) {
+      // the min..max part is empty, only the includes are left
+      if include.is_empty() {
+        return Range::none();
+      }
+      return Range { min: None, max: None, except, include };
+    }
+    let min = match (&self.min, &other.min) {
+      (Some(a), Some(b)) => Some(a.max(b).clone()),
+      (a, b) => a.clone().or(b.clone()),
+    };
+    let max = match (&self.max, &other.max) {
+      (Some(a), Some(b)) => Some(a.min(b).clone()),
+      (a, b) => a.clone().or(b.clone()),
+    };
+    Range { min, max, except, include }
+  }
   pub fn is_any(&self) -> bool { // is empty or is just >= 0.0.0
     (self.min.clone().is_none() || self.min.clone().unwrap() == Version::new(0, 0, 0))
       && self.max.is_none()
@@ -365,6 +483,111 @@ impl Range {
     let range : Self = parse_range(&range.into())?;
     Ok(range)
   }
+  /// Like parse, but also takes the operators people write by mistake (=>, =<, ≥, ≤) and RubyGems' and PEP 440's
+  /// ~> and ~= (~>2.1 is >=2.1 <3), with a warning for each one that was read differently.
+  /// Every comparator has to match, so =>1.5 ~>1.2 is >=1.5 <2 (parse would keep the loosest bounds).
+  /// Errors point into range as it was given, an error in something that was rewritten points at its start.
+  pub fn parse_lenient<S:Into<String>>(range: S) -> Result<(Self, Vec<Warning>), ParseError> {
+    let range = range.into();
+    let (respelled, warnings, rewrites) = diagnostics::respell_operators(&range);
+    match parse_comparators(&respelled) {
+      Ok(comparators) => Ok((comparators.iter().fold(Range::any(), |range, c| range.intersect(c)), warnings)),
+      Err(mut e) => {
+        e.location = diagnostics::original_location(&range, &rewrites, e.location.offset);
+        Err(e)
+      }
+    }
+  }
+
+  /// The smallest change to this range that lets version in, in the given style. For ^1.2 and 2.0.0:
+  /// Alternative is ^1.2.0 || ^2.0.0, Bound is >=1.2.0,<3.0.0 and Bump is ^2.0.0.
+  /// A range that already contains version is returned as it is.
+  pub fn widen_to_include(&self, version: &Version, style: WidenStyle) -> RangeSet {
+    if self.contains(version.clone()) {
+      return RangeSet::from(self.clone());
+    }
+    // builds don't belong in ranges
+    let version = version.with_build(None::<String>);
+    let caret = Range::from_ver_vec(vec![(Op::Caret, version.clone())]);
+    match style {
+      WidenStyle::Alternative if self.is_empty() => RangeSet::from(caret),
+      WidenStyle::Alternative => RangeSet::new(vec![self.clone(), caret]),
+      WidenStyle::Bump if self.to_caret().is_some() => RangeSet::from(caret),
+      WidenStyle::Bump if self.to_tilde().is_some() => RangeSet::from(Range::from_ver_vec(vec![(Op::Tilde, version)])),
+      WidenStyle::Bump if self.is_exact_match() => RangeSet::from(Range::from_ver_vec(vec![(Op::Eq, version)])),
+      WidenStyle::Bound | WidenStyle::Bump => RangeSet::from(self.bound_to_include(version)),
+    }
+  }
+  // moves min down or max up, =1.2.3 gets another include
+  fn bound_to_include(&self, version: Version) -> Range {
+    let mut range = self.clone();
+    range.except.retain(|v| *v != version);
+    if range.contains(version.clone()) {
+      return range;
+    }
+    if range.min.is_none() && range.max.is_none() {
+      range.include.push(version);
+      return range;
+    }
+    if range.min.as_ref().is_some_and(|min| version < *min) {
+      range.min = Some(version.clone());
+    }
+    // the new max is as precise as the old one: <2.0.0 -> <3.0.0, <1.3.0 -> <1.4.0
+    if let Some(max) = range.max.as_ref().filter(|max| version >= **max) {
+      range.max = Some(match (max.minor, max.patch) {
+        (0, 0) => Version::new(version.major + 1, 0, 0),
+        (_, 0) => Version::new(version.major, version.minor + 1, 0),
+        _ => Version::new(version.major, version.minor, version.patch + 1),
+      });
+    }
+    range
+  }
+
+  /// =version, nothing else. None if this range doesn't contain version, like ^1.2 pinned to 5.0.0
+  pub fn pin(&self, version: &Version) -> Option<Range> {
+    if !self.contains(version.clone()) {
+      return None;
+    }
+    Some(Range { min: None, max: None, except: vec![], include: vec![version.with_build(None::<String>)] })
+  }
+  /// The part of this range in ~min: >=1.2 -> ~1.2.0, ^1.2.3 -> ~1.2.3, >=1.2 <1.2.5 stays
+  pub fn tighten_to_tilde(&self) -> Range {
+    self.tighten(Op::Tilde)
+  }
+  /// The part of this range in ^min: >=1.2 -> ^1.2.0, >=1.2 <1.5 stays
+  pub fn tighten_to_caret(&self) -> Range {
+    self.tighten(Op::Caret)
+  }
+  fn tighten(&self, op: Op) -> Range {
+    // =1.2.3 can't get any tighter
+    if self.min.is_none() && self.max.is_none() && !self.include.is_empty() {
+      return self.clone();
+    }
+    let min = self.min.clone().unwrap_or(Version::new(0, 0, 0));
+    self.intersect(&Range::from_ver_vec(vec![(op, min)])).without_irrelevant()
+  }
+  /// The range from version on (>=version) with the same max, a min that's already higher is kept and older includes are dropped.
+  /// None if that leaves nothing, like ^1.2 raised to 2.0.0 or =1.2.3 to 1.3.0
+  pub fn raise_minimum(&self, version: &Version) -> Option<Range> {
+    let version = version.with_build(None::<String>);
+    let mut range = self.clone();
+    // =1.2.3 would contain everything above version with a min
+    let only_include = range.min.is_none() && range.max.is_none() && !range.include.is_empty();
+    if !only_include && range.min.as_ref().map_or(true, |min| version > *min) {
+      range.min = Some(version.clone());
+    }
+    range.include.retain(|v| *v >= version);
+    let range = range.without_irrelevant();
+    let nothing_left = range.is_empty() || (only_include && range.include.is_empty());
+    (!nothing_left).then_some(range)
+  }
+  // drops the excepts that are out of min..max, they don't exclude anything anymore
+  fn without_irrelevant(mut self) -> Range {
+    let (min, max) = (&self.min, &self.max);
+    self.except.retain(|v| min.as_ref().map_or(true, |min| v >= min) && max.as_ref().map_or(true, |max| v < max));
+    self
+  }
+
   fn tilde_range_to_vec(version: Version) -> Vec<(Op, Version)> {
     // ~1.2.3 -> >=1.2.3 <1.3.0
     // ~1.2 -> >=1.2.0 <1.3.0
@@ -403,6 +626,27 @@ impl Range {
   fn gt_range_to_vec(version:Version) ->  Vec<(Op, Version)> {Self::gt_range_to_ge(version)}
 }
 
+impl RangeSet {
+  pub fn new(ranges: Vec<Range>) -> Self {
+    Self { ranges }
+  }
+  pub fn contains(&self, version: Version) -> bool {
+    self.ranges.iter().any(|range| range.contains(version.clone()))
+  }
+  pub fn is_any(&self) -> bool {
+    self.ranges.iter().any(|range| range.is_any())
+  }
+  pub fn is_empty(&self) -> bool {
+    self.ranges.iter().all(|range| range.is_empty())
+  }
+}
+
+impl From<Range> for RangeSet {
+  fn from(range: Range) -> Self {
+    Self::new(vec![range])
+  }
+}
+
 impl Default for Version {
   fn default() -> Self {
     Self {
@@ -464,11 +708,12 @@ impl Ord for Version {
 impl Display for Version {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     let mut s = format!("{}.{}.{}", self.major, self.minor, self.patch);
+    // RadixNum's Display is ALPHAr36, this is the text it was read from
     if let Some(extra_version) = &self.extra_version {
-      s.push_str(&format!(".{}", extra_version));
+      s.push_str(&format!(".{}", from_radix(extra_version)));
     }
     if let Some(pre_release) = &self.pre_release {
-      s.push_str(&format!("-{}", pre_release));
+      s.push_str(&format!("-{}", from_radix(pre_release)));
     }
     if let Some(build) = &self.build {
       s.push_str(&format!("+{}", build));
@@ -483,20 +728,17 @@ impl Display for Range {
     if self.is_any() {
       return write!(f, "*");
     }
-    // special check to prefer ^ and ~ when possible
-    if let Some(caret) = self.to_caret() {
-      return write!(f, "{}", caret);
-    }
-    if let Some(tilde) = self.to_tilde() {
-      return write!(f, "{}", tilde);
-    }
-
     let mut s = String::new();
-    if let Some(min) = &self.min {
-      s.push_str(&format!(">={},", min.to_string()));
-    }
-    if let Some(max) = &self.max {
-      s.push_str(&format!("<{},", max.to_string()));
+    // special check to prefer ^ and ~ when possible, the != and = still go after them
+    if let Some(shorthand) = self.to_caret().or_else(|| self.to_tilde()) {
+      s.push_str(&format!("{},", shorthand));
+    } else {
+      if let Some(min) = &self.min {
+        s.push_str(&format!(">={},", min.to_string()));
+      }
+      if let Some(max) = &self.max {
+        s.push_str(&format!("<{},", max.to_string()));
+      }
     }
     for except in &self.except {
       s.push_str(&format!("!={},", except.to_string()));
@@ -509,6 +751,25 @@ impl Display for Range {
   }
 }
 
+impl Display for RangeSet {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let s = self.ranges.iter().map(|range| range.to_string()).collect::<Vec<_>>().join(" || ");
+    write!(f, "{}", s)
+  }
+}
+
+/// How Range::widen_to_include writes the widened range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WidenStyle {
+  // keep the range and add ^version as an alternative: ^1.2 || ^2.0
+  Alternative,
+  // move the bound that keeps version out: >=1.2 <3
+  Bound,
+  // the same operator on the new version: ^1.2 -> ^2.0, ~1.2 -> ~1.3, =1.2.3 -> =1.3.0
+  // ranges that aren't ^, ~ or = are widened like Bound
+  Bump,
+}
+
 #[derive(PartialEq, Eq, Hash)]
 pub enum Op {
   Eq,    // ==
@@ -1,13 +1,35 @@
 mod implementations;
+pub mod debian;
+pub mod rpm;
+pub mod rubygems;
+pub mod composer;
+pub mod gomod;
+pub mod pep508;
+pub mod calver;
+pub mod scheme;
+pub mod strict;
+pub mod lossless;
+pub mod diagnostics;
+pub mod tags;
+pub mod resolve;
+pub mod lockfile;
+pub mod outdated;
+pub mod npm;
+pub mod python;
+pub mod cli;
+#[cfg(feature = "toml")]
+pub mod cargo;
+mod written;
 #[cfg(test)]
 mod tests;
 
 use thiserror::Error;
-use crate::implementations::{Version, Range, Op};
+pub use crate::implementations::{Version, Range, RangeSet, Op, WidenStyle};
+use crate::lossless::Spans;
 pub type ParseError = peg::error::ParseError<peg::str::LineCol>;
 
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Dependency {
   pub name: String,
   pub range: Range
@@ -18,7 +40,7 @@ peg::parser!( pub grammar Parser() for str {
     = " "* v:version() " "* ![_] {v} // ![_] means end of file
 
   rule version() -> Version
-    = ['v' | 'V']? " "? m:main() e:extra()? a:afterV() {
+    = ['v' | 'V']? " "? m:main() e:extra()? a:afterV() {?
       Version::new_w_extra(
         m.0,
         m.1.unwrap_or(0),
@@ -26,7 +48,7 @@ peg::parser!( pub grammar Parser() for str {
         e,
         a.0,
         a.1
-      ).unwrap()
+      ).or(Err("an extra version and pre-release of at most 12 letters and digits"))
   }
   // pre and build any order and existence
   rule afterV() -> (Option<String>, Option<String>)
@@ -59,6 +81,48 @@ peg::parser!( pub grammar Parser() for str {
   rule pre() -> String
     = "-" c:chars() { c }
 
+  // same as parse_version, but only says where each part is so the text can be kept
+  pub rule parse_lossless() -> Spans
+    = " "* ['v' | 'V']? " "? M:spanned(<num()>) "."? m:spanned(<num()>)? "."? p:spanned(<num()>)?
+      e:("." e:spanned(<storable(<alphanumeric()>)>) { e })? a:lossless_after() " "* ![_] {
+      Spans { major: M, minor: m, patch: p, extra: e, pre: a.0, build: a.1 }
+    }
+  rule lossless_after() -> (Option<lossless::Span>, Option<lossless::Span>)
+    = p:("-" p:spanned(<storable(<chars()>)>) { p })? b:("+" b:spanned(<chars()>) { b })? supOrEnd() { (p, b) }
+    / "+" b:spanned(<chars()>) "-" p:spanned(<storable(<chars()>)>) supOrEnd() { (Some(p), Some(b)) }
+  // the extra version can't have dots, 1.2.3.4.5 would be the same Version as 1.2.3.45
+  rule alphanumeric() -> String
+    = n:$(['a'..='z' | 'A'..='Z' | '0'..='9']+) { n.to_string() }
+  // what r reads, if it still fits in a Version once it's normalized (RC.01 -> rc01)
+  rule storable(r: rule<String>) -> String
+    = c:r() {?
+      match lossless::normalize(&c) {
+        Some(n) if Version::new(0, 0, 0).with_pre(Some(n.as_str())).is_err() => Err("a shorter identifier"),
+        _ => Ok(c),
+      }
+    }
+  // a whole build, as build() reads it
+  pub rule parse_build() -> String
+    = c:chars() ![_] { c }
+  rule spanned<T>(r: rule<T>) -> lossless::Span
+    = s:position!() r() e:position!() { s..e }
+
+  // a version at the start of some text, returns where it ended. Stricter than version() so words and
+  // numbers aren't versions: it needs a dot or a v (3.4, v3), and dots, hyphens and build-before-pre aren't skipped
+  pub rule find_version() -> (Version, usize)
+    = n:text_numbers() e:("." e:$(['0'..='9']+) { e })? p:("-" p:text_identifiers() { p })? b:("+" b:text_identifiers() { b })?
+      end:position!() !['a'..='z' | 'A'..='Z' | '0'..='9'] [_]* {?
+      let normalize = |s: &str| s.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase();
+      Version::new_w_extra(n.0, n.1, n.2, e, p.map(normalize).as_deref(), b.map(|b| b.to_lowercase()).as_deref())
+        .map(|v| (v, end))
+        .or(Err("version"))
+    }
+  rule text_numbers() -> (u32, u32, u32)
+    = ['v' | 'V'] M:num() m:("." m:num() { m })? p:("." p:num() { p })? { (M, m.unwrap_or(0), p.unwrap_or(0)) }
+    / M:num() "." m:num() p:("." p:num() { p })? { (M, m, p.unwrap_or(0)) }
+  rule text_identifiers() -> &'input str
+    = $((['a'..='z' | 'A'..='Z' | '0'..='9']+) ++ ".")
+
   // TODO implement star version (1.0.*) if enough traffic
   // TODO add support for *+build for things like *+windows
   pub rule parse_range() -> Range
@@ -66,10 +130,15 @@ peg::parser!( pub grammar Parser() for str {
     = " "* "*" " "* ![_] { Range::any() }
     / " "* r:(range() ** "") " "* ![_] { Range::from_ver_vec(r) }
 
+  // parse_range with each comparator as its own Range, so they can be intersected
+  pub rule parse_comparators() -> Vec<Range>
+    = " "* "*" " "* ![_] { vec![Range::any()] }
+    / " "* r:(range() ** "") " "* ![_] { r.into_iter().map(|r| Range::from_ver_vec(vec![r])).collect() }
+
   rule range() -> (Op, Version)
     = o:op() " "* v:version() " "* { (o,v) }
 
   rule op() -> Op
     = o:$("==" / "!=" / "<=" / ">=" / "=" / "<" / ">" / "~" / "^" / " " / "") { Op::from_str(o).unwrap() }
-      // => and =< will fail, but that's ok
+      // => and =< will fail, but that's ok (Range::parse_lenient takes them)
 });
\ No newline at end of file
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::Dependency;
+use crate::implementations::{Range, Version};
+use crate::resolve::Provider;
+use crate::scheme::{SemVer, VersionScheme};
+
+const HEADER: &str = "# yasemver lockfile, one package per line: name version range";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locked {
+  pub version: Version,
+  // the range the version was picked for
+  pub range: Range,
+}
+
+/// Pinned versions, written sorted by name so the same lockfile is always the same text:
+/// bar 2.5.0 ^2.0.0
+/// foo 1.1.0 >=1.0.0,<1.5.0
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Lockfile {
+  pub packages: BTreeMap<String, Locked>,
+}
+
+/// A difference between a lockfile and what the manifest asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drift {
+  // the locked version isn't in the range anymore
+  Unsatisfied { name: String, locked: Version, range: Range },
+  // the manifest has a dependency that isn't locked
+  Missing { name: String, range: Range },
+  // a newer version in the range is available
+  Upgradable { name: String, locked: Version, latest: Version },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LockfileError {
+  #[error("line {line}: {content} should be name version range")]
+  InvalidLine { line: usize, content: String },
+  #[error("line {line}: {name} is locked twice")]
+  Duplicate { line: usize, name: String },
+}
+
+impl Lockfile {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert<S: Into<String>>(&mut self, name: S, version: Version, range: Range) -> &mut Self {
+    self.packages.insert(name.into(), Locked { version, range });
+    self
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Locked> {
+    self.packages.get(name)
+  }
+
+  pub fn parse<S: Into<String>>(lockfile: S) -> Result<Self, LockfileError> {
+    let lockfile = lockfile.into();
+    let mut packages = BTreeMap::new();
+    for (i, line) in lockfile.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let invalid = || LockfileError::InvalidLine { line: i + 1, content: line.to_string() };
+      let mut parts = line.splitn(3, ' ');
+      let (Some(name), Some(version), Some(range)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+      };
+      let locked = Locked {
+        version: Version::parse(version).map_err(|_| invalid())?,
+        range: Range::parse(range).map_err(|_| invalid())?,
+      };
+      if packages.insert(name.to_string(), locked).is_some() {
+        return Err(LockfileError::Duplicate { line: i + 1, name: name.to_string() });
+      }
+    }
+    Ok(Self { packages })
+  }
+
+  // the range each locked package has to be in, the manifest's if it's there and the one it was locked with if not
+  fn ranges<'a>(&'a self, manifest: &'a [Dependency]) -> impl Iterator<Item = (&'a String, &'a Locked, &'a Range)> {
+    self.packages.iter().map(move |(name, locked)| {
+      let range = manifest.iter().find(|d| d.name == *name).map_or(&locked.range, |d| &d.range);
+      (name, locked, range)
+    })
+  }
+
+  /// What doesn't match the manifest anymore: locked versions out of their range and dependencies that aren't locked.
+  /// Packages that aren't in the manifest (dependencies of dependencies) are checked against the range they were locked with.
+  pub fn check(&self, manifest: &[Dependency]) -> Vec<Drift> {
+    let mut drifts: Vec<Drift> = self.ranges(manifest)
+      .filter(|(_, locked, range)| !range.contains(locked.version.clone()))
+      .map(|(name, locked, range)| Drift::Unsatisfied { name: name.clone(), locked: locked.version.clone(), range: range.clone() })
+      .collect();
+    drifts.extend(manifest.iter()
+      .filter(|d| !self.packages.contains_key(&d.name))
+      .map(|d| Drift::Missing { name: d.name.clone(), range: d.range.clone() }));
+    drifts
+  }
+
+  /// check, plus the locked packages that have a newer version in their range
+  pub fn check_upgrades<P: Provider>(&self, manifest: &[Dependency], provider: &P) -> Vec<Drift> {
+    let mut drifts = self.check(manifest);
+    for (name, locked, range) in self.ranges(manifest) {
+      let latest = provider.versions(name).into_iter()
+        .filter(|v| range.contains(v.clone()))
+        .max_by(|a, b| SemVer.cmp(a, b));
+      if let Some(latest) = latest.filter(|latest| SemVer.cmp(latest, &locked.version).is_gt()) {
+        drifts.push(Drift::Upgradable { name: name.clone(), locked: locked.version.clone(), latest });
+      }
+    }
+    drifts
+  }
+}
+
+impl Display for Lockfile {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "{}", HEADER)?;
+    for (name, locked) in &self.packages {
+      writeln!(f, "{} {} {}", name, locked.version, locked.range)?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Lockfile {
+  type Err = LockfileError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+impl Display for Drift {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Unsatisfied { name, locked, range } => write!(f, "{} is locked at {}, which isn't in {}", name, locked, range),
+      Self::Missing { name, range } => write!(f, "{} {} isn't locked", name, range),
+      Self::Upgradable { name, locked, latest } => write!(f, "{} is locked at {}, {} is available", name, locked, latest),
+    }
+  }
+}
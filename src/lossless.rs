@@ -0,0 +1,183 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::ParseError;
+use crate::Parser::{parse_build, parse_lossless};
+use crate::implementations::{from_radix, Version};
+
+// byte offsets in the original text
+pub type Span = std::ops::Range<usize>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+  Major,
+  Minor,
+  Patch,
+  Extra,
+  Pre,
+  Build,
+}
+
+impl Display for Component {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::Major => "major",
+      Self::Minor => "minor",
+      Self::Patch => "patch",
+      Self::Extra => "extra version",
+      Self::Pre => "pre-release",
+      Self::Build => "build",
+    })
+  }
+}
+
+// where each component was written, without its separator (1.0-RC.01 has pre at 4..9)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Spans {
+  pub major: Span,
+  pub minor: Option<Span>,
+  pub patch: Option<Span>,
+  pub extra: Option<Span>,
+  pub pre: Option<Span>,
+  pub build: Option<Span>,
+}
+
+/// A Version that remembers how it was written, "v1.0-RC.01" stays "v1.0-RC.01" when printed.
+/// version has the normalized Version, which can't store dots or underscores in the pre-release,
+/// so those are dropped there (RC.01 -> rc01) but kept in the text. That makes 1.0-rc.1, 1.0-rc_1
+/// and 1.0-RC1 the same version, only the text tells them apart; Version::parse rejects the first two.
+/// The extra version can't have them at all, 1.2.3.4.5 is an error and not 1.2.3.45
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LosslessVersion {
+  pub version: Version,
+  original: String,
+  pub spans: Spans,
+}
+
+// what Version can store, lowercase letters and digits
+pub(crate) fn normalize(s: &str) -> Option<String> {
+  let s: String = s.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase();
+  (!s.is_empty()).then_some(s)
+}
+
+impl LosslessVersion {
+  pub fn parse<S: Into<String>>(version: S) -> Result<Self, ParseError> {
+    let original = version.into();
+    let spans = parse_lossless(&original)?;
+    let text = |span: &Option<Span>| span.clone().map(|s| &original[s]);
+    let number = |span: &Option<Span>| text(span).map_or(0, |n| n.parse().unwrap());
+    // only letters and digits are left, and the grammar checked they fit
+    let version = Version::new_w_extra(
+      number(&Some(spans.major.clone())),
+      number(&spans.minor),
+      number(&spans.patch),
+      text(&spans.extra).and_then(normalize),
+      text(&spans.pre).and_then(normalize),
+      text(&spans.build).map(|b| b.to_lowercase()),
+    ).unwrap();
+    Ok(Self { version, original, spans })
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.original
+  }
+
+  pub fn span(&self, component: Component) -> Option<Span> {
+    match component {
+      Component::Major => Some(self.spans.major.clone()),
+      Component::Minor => self.spans.minor.clone(),
+      Component::Patch => self.spans.patch.clone(),
+      Component::Extra => self.spans.extra.clone(),
+      Component::Pre => self.spans.pre.clone(),
+      Component::Build => self.spans.build.clone(),
+    }
+  }
+
+  /// Whether the component was written, "1.2" has no patch even though version.patch is 0
+  pub fn is_specified(&self, component: Component) -> bool {
+    self.span(component).is_some()
+  }
+
+  /// The text as written, as it was written
+  pub fn text(&self, component: Component) -> Option<&str> {
+    self.span(component).map(|s| &self.original[s])
+  }
+
+  /// Rewrites the text to be version, only touching what changed:
+  /// "v1.2" with 1.3.0 -> "v1.3", "V 1.0-RC.01" with 2.0.0-rc01 -> "V 2.0-RC.01", "1.2" with 1.2.5 -> "1.2.5".
+  /// Fails if version's build can't be read back as it is, like a/b or linux-x64 (build linux and pre-release x64)
+  pub fn with_version(&self, version: &Version) -> Result<Self, ParseError> {
+    if let Some(build) = &version.build {
+      parse_build(build)?;
+    }
+    let old = &self.version;
+    let spans = &self.spans;
+    // (where, replace until, new text), applied from the end so the offsets stay valid
+    let mut edits: Vec<(usize, usize, String)> = vec![];
+    let mut number = |span: &Option<Span>, after: usize, old: u32, new: u32, needed: bool| match span {
+      Some(s) if old != new => edits.push((s.start, s.end, new.to_string())),
+      None if needed => edits.push((after, after, format!(".{}", new))),
+      _ => {}
+    };
+    let needs_patch = version.patch != 0;
+    let needs_minor = version.minor != 0 || needs_patch;
+    number(&Some(spans.major.clone()), 0, old.major, version.major, true);
+    number(&spans.minor, spans.major.end, old.minor, version.minor, needs_minor);
+    let minor_end = spans.minor.as_ref().map_or(spans.major.end, |s| s.end);
+    number(&spans.patch, minor_end, old.patch, version.patch, needs_patch);
+
+    let extra = version.extra_version.as_ref().map(from_radix);
+    // an extra version needs the patch before it, if it wasn't written (or inserted above) it's 0
+    if extra.is_some() && spans.patch.is_none() && spans.extra.is_none() && !needs_patch {
+      let zeros = if spans.minor.is_some() || needs_minor { ".0" } else { ".0.0" };
+      edits.push((minor_end, minor_end, zeros.to_string()));
+    }
+    let mut core_end = spans.patch.as_ref().map_or(minor_end, |s| s.end);
+    let mut component = |span: &Option<Span>, at: usize, separator: char, changed: bool, new: Option<String>| match (span, new) {
+      (Some(s), Some(new)) if changed => edits.push((s.start, s.end, new)),
+      (Some(s), None) => edits.push((s.start - 1, s.end, String::new())),
+      (None, Some(new)) => edits.push((at, at, format!("{}{}", separator, new))),
+      _ => {}
+    };
+    component(&spans.extra, core_end, '.', old.extra_version != version.extra_version, extra);
+    core_end = spans.extra.as_ref().map_or(core_end, |s| s.end);
+
+    // pre and build go where they were, or after each other
+    let build_first = matches!((&spans.build, &spans.pre), (Some(b), Some(p)) if b.start < p.start);
+    let pre_at = match &spans.build {
+      Some(b) if build_first => b.end,
+      _ => core_end,
+    };
+    let build_at = spans.pre.as_ref().filter(|_| !build_first).map_or(core_end, |p| p.end);
+    component(&spans.pre, pre_at, '-', old.pre_release != version.pre_release, version.pre_release.as_ref().map(from_radix));
+    let build_changed = old.build != version.build.as_ref().map(|b| b.to_lowercase());
+    component(&spans.build, build_at, '+', build_changed, version.build.clone());
+
+    let mut text = self.original.clone();
+    // stable so edits at the same place keep their order (the patch before the extra version)
+    edits.sort_by_key(|e| e.0);
+    for (start, end, new) in edits.into_iter().rev() {
+      text.replace_range(start..end, &new);
+    }
+    Self::parse(text)
+  }
+}
+
+impl Display for LosslessVersion {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.original)
+  }
+}
+
+impl FromStr for LosslessVersion {
+  type Err = ParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+impl From<LosslessVersion> for Version {
+  fn from(version: LosslessVersion) -> Self {
+    version.version
+  }
+}
@@ -0,0 +1,8 @@
+use std::io;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let code = yasemver::cli::run(&args, io::stdin().lock(), &mut io::stdout().lock(), &mut io::stderr().lock());
+  ExitCode::from(code)
+}
@@ -0,0 +1,343 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::{Dependency, ParseError};
+use crate::implementations::{Op, Range, RangeSet};
+use crate::written::{caret_max, tilde_max, Written};
+use crate::lossless::Span;
+
+// the dependency objects of a package.json, in the order they're read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+  Dependencies,
+  DevDependencies,
+  PeerDependencies,
+  OptionalDependencies,
+}
+
+/// What a package.json says to install, only Range and Alias are versions of a registry package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Spec {
+  // ^1.2.3, >=1 <2 || 3.x
+  Range(RangeSet),
+  // latest, next
+  Tag(String),
+  // npm:string-width@^4, another package installed under this name
+  Alias { package: String, range: RangeSet },
+  // workspace:^, workspace:*, workspace:^1.2.0, a package of the same monorepo
+  Workspace(String),
+  // file:../foo, link:../foo, ./foo
+  File(String),
+  // git+https://..., github:user/repo, user/repo#v1.0.0
+  Git(String),
+  // https://example.com/foo.tgz
+  Url(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NpmDependency {
+  // the key in package.json, the name it's installed under
+  pub name: String,
+  pub section: Section,
+  pub spec: Spec,
+  // as written
+  pub text: String,
+}
+
+/// A package.json that's only changed where a spec is replaced, key order, spacing and everything else are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageJson {
+  text: String,
+  dependencies: Vec<NpmDependency>,
+  // where the string of each dependency is, without the quotes
+  spans: Vec<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NpmError {
+  #[error("invalid package.json at {0}")]
+  Json(usize),
+  #[error("{name} has an invalid spec: {spec}")]
+  InvalidSpec { name: String, spec: String },
+  #[error("{0} isn't in the package.json")]
+  NotFound(String),
+  #[error("{0} isn't a version range, it can't be given one")]
+  NotARange(String),
+  #[error("{0} can't be written as an npm range")]
+  Unrepresentable(String),
+}
+
+impl Section {
+  pub fn key(&self) -> &'static str {
+    match self {
+      Self::Dependencies => "dependencies",
+      Self::DevDependencies => "devDependencies",
+      Self::PeerDependencies => "peerDependencies",
+      Self::OptionalDependencies => "optionalDependencies",
+    }
+  }
+}
+
+impl Spec {
+  /// Reads a spec like npm does, anything that isn't a path, url, git repository or range is a tag
+  pub fn parse(spec: &str) -> Option<Self> {
+    let spec = spec.trim();
+    if let Some(alias) = spec.strip_prefix("npm:") {
+      // the name can start with @ (@scope/name@^1)
+      let (package, range) = match alias.get(1..).and_then(|rest| rest.find('@')) {
+        Some(i) => (&alias[..i + 1], &alias[i + 2..]),
+        None => (alias, ""),
+      };
+      return Some(Self::Alias { package: package.to_string(), range: parse_range(range).ok()? });
+    }
+    if let Some(workspace) = spec.strip_prefix("workspace:") {
+      return Some(Self::Workspace(workspace.to_string()));
+    }
+    let starts_with = |prefixes: &[&str]| prefixes.iter().any(|p| spec.starts_with(p));
+    if starts_with(&["file:", "link:", "./", "../", "/", "~/"]) {
+      return Some(Self::File(spec.to_string()));
+    }
+    if starts_with(&["git+", "git://", "github:", "gitlab:", "bitbucket:", "gist:"]) {
+      return Some(Self::Git(spec.to_string()));
+    }
+    if starts_with(&["http://", "https://"]) {
+      return Some(Self::Url(spec.to_string()));
+    }
+    if let Ok(range) = parse_range(spec) {
+      return Some(Self::Range(range));
+    }
+    // user/repo is on GitHub
+    let name = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-._".contains(c));
+    let repository = spec.split('#').next().unwrap_or(spec);
+    if matches!(repository.split_once('/'), Some((user, repo)) if name(user) && name(repo)) {
+      return Some(Self::Git(spec.to_string()));
+    }
+    // 1.0.0-alpha.20240101 is a range we can't store, not a tag
+    (name(spec) && !spec.starts_with(|c: char| c.is_ascii_digit())).then(|| Self::Tag(spec.to_string()))
+  }
+}
+
+impl NpmDependency {
+  /// The package and range, None if it isn't a registry package or its range has ||
+  pub fn to_dependency(&self) -> Option<Dependency> {
+    let (name, ranges) = match &self.spec {
+      Spec::Range(ranges) => (&self.name, ranges),
+      Spec::Alias { package, range } => (package, range),
+      _ => return None,
+    };
+    match ranges.ranges.as_slice() {
+      [range] => Some(Dependency { name: name.clone(), range: range.clone() }),
+      _ => None,
+    }
+  }
+}
+
+impl PackageJson {
+  pub fn parse<S: Into<String>>(package_json: S) -> Result<Self, NpmError> {
+    let text = package_json.into();
+    let root = json_parser::document(&text).map_err(|e| NpmError::Json(e.location.offset))?;
+    let mut dependencies = vec![];
+    let mut spans = vec![];
+    for section in [Section::Dependencies, Section::DevDependencies, Section::PeerDependencies, Section::OptionalDependencies] {
+      let Some(Json::Object(entries, _)) = root.get(section.key()) else { continue };
+      for (name, value) in entries {
+        let Json::String(spec, span) = value else {
+          return Err(NpmError::InvalidSpec { name: name.clone(), spec: text[value.span()].to_string() });
+        };
+        let invalid = || NpmError::InvalidSpec { name: name.clone(), spec: spec.clone() };
+        dependencies.push(NpmDependency { name: name.clone(), section, spec: Spec::parse(spec).ok_or_else(invalid)?, text: spec.clone() });
+        spans.push(span.clone());
+      }
+    }
+    Ok(Self { text, dependencies, spans })
+  }
+
+  pub fn dependencies(&self) -> &[NpmDependency] {
+    &self.dependencies
+  }
+
+  pub fn get(&self, section: Section, name: &str) -> Option<&NpmDependency> {
+    self.dependencies.iter().find(|d| d.section == section && d.name == name)
+  }
+
+  /// Replaces the spec of dependency with range, keeping the npm: and workspace: prefixes.
+  /// Tags become ranges, paths, urls and git repositories can't.
+  pub fn set_range(&mut self, dependency: &NpmDependency, range: &RangeSet) -> Result<(), NpmError> {
+    let i = self.dependencies.iter().position(|d| d.section == dependency.section && d.name == dependency.name)
+      .ok_or_else(|| NpmError::NotFound(dependency.name.clone()))?;
+    let text = to_range(range).ok_or_else(|| NpmError::Unrepresentable(range.to_string()))?;
+    let spec = match &self.dependencies[i].spec {
+      Spec::Range(_) | Spec::Tag(_) => text,
+      Spec::Alias { package, .. } => format!("npm:{}@{}", package, text),
+      Spec::Workspace(_) => format!("workspace:{}", text),
+      _ => return Err(NpmError::NotARange(dependency.name.clone())),
+    };
+    self.set_spec(i, &spec)
+  }
+
+  fn set_spec(&mut self, i: usize, spec: &str) -> Result<(), NpmError> {
+    let escaped = spec.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut text = self.text.clone();
+    text.replace_range(self.spans[i].clone(), &escaped);
+    // the spans after this one moved
+    *self = Self::parse(text)?;
+    Ok(())
+  }
+}
+
+impl Display for PackageJson {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.text)
+  }
+}
+
+impl FromStr for PackageJson {
+  type Err = NpmError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+// ranges as npm would write them, =1.2.3 is 1.2.3 and includes are alternatives. None if there's a !=
+fn to_range(ranges: &RangeSet) -> Option<String> {
+  let mut alternatives = vec![];
+  for range in &ranges.ranges {
+    if !range.except.is_empty() {
+      return None;
+    }
+    let bounds = match (&range.min, &range.max) {
+      (None, None) if !range.include.is_empty() => None,
+      (None, None) => Some("*".to_string()),
+      (Some(min), Some(max)) if Some(max) == caret_max(min).as_ref() => Some(format!("^{}", min)),
+      (Some(min), Some(max)) if Some(max) == tilde_max(min).as_ref() => Some(format!("~{}", min)),
+      (Some(min), Some(max)) => Some(format!(">={} <{}", min, max)),
+      (Some(min), None) => Some(format!(">={}", min)),
+      (None, Some(max)) => Some(format!("<{}", max)),
+    };
+    alternatives.extend(bounds);
+    alternatives.extend(range.include.iter().map(|v| v.to_string()));
+  }
+  Some(alternatives.join(" || "))
+}
+
+/// An npm range ("^1.2", "1.x || >=2.5.0", "1.2 - 2.3.4") as a RangeSet.
+/// ^ keeps the first number that isn't 0 (^0.3 -> >=0.3.0 <0.4.0), an empty range is *.
+/// Fails on pre-releases too long for a Version once their dots are dropped (1.0.0-alpha.20240101)
+pub fn parse_range<S: Into<String>>(range: S) -> Result<RangeSet, ParseError> {
+  npm_parser::parse_range(&range.into())
+}
+
+// what op means in front of the version as npm reads it, the x and * at the end aren't in the numbers: 1.2.x -> [1, 2]
+fn with_op(written: &Written, op: &str) -> Result<Range, &'static str> {
+  let partial = written.is_partial();
+  Ok(match op {
+    // *, x
+    _ if written.numbers.is_empty() => match op {
+      ">" | "<" => Range::none(),
+      _ => Range::any(),
+    },
+    "" | "=" if partial => written.between(Written::next(&written.numbers)?)?,
+    "" | "=" => Range::from_ver_vec(vec![(Op::Eq, written.to_version()?)]),
+    "^" => written.caret()?,
+    "~" | "~>" => written.between(Written::next(&written.numbers[..written.numbers.len().min(2)])?)?,
+    ">" if partial => Range::from_ver_vec(vec![(Op::Ge, Written::next(&written.numbers)?)]),
+    "<=" if partial => Range::from_ver_vec(vec![(Op::Lt, Written::next(&written.numbers)?)]),
+    _ => Range::from_ver_vec(vec![(Op::from_str(op).unwrap(), written.to_version()?)]),
+  })
+}
+
+// 1.2 - 2.3.4 -> >=1.2.0 <=2.3.4, 1.2.3 - 2.3 -> >=1.2.3 <2.4.0
+fn hyphen(from: &Written, to: &Written) -> Result<Range, &'static str> {
+  let from = with_op(from, ">=")?;
+  Ok(match to.numbers.len() {
+    0 => from,
+    3 => from.intersect(&with_op(to, "<=")?),
+    _ => from.intersect(&Range::from_ver_vec(vec![(Op::Lt, Written::next(&to.numbers)?)])),
+  })
+}
+
+peg::parser!( grammar npm_parser() for str {
+  rule _() = [' ' | '\t']*
+
+  rule num() -> u32
+    = n:$(['0'..='9']+) {? n.parse().or(Err("number")) }
+  rule wildcard() = ['x' | 'X' | '*']
+
+  // beta.2 -> beta2, dots and hyphens can't be stored
+  rule identifiers() -> String
+    = i:$(['a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-']+) { i.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase() }
+
+  rule version() -> Written
+    = ['v' | 'V']? n:(num() **<1,3> ".") ("." wildcard())* p:("-" p:identifiers() { p })? ("+" identifiers())? {
+      Written::new(n, p.filter(|p| !p.is_empty()))
+    }
+    / wildcard() ("." wildcard())* { Written::new(vec![], None) }
+
+  rule op() -> &'input str
+    = $(">=" / "<=" / "~>" / "=" / ">" / "<" / "~" / "^" / "")
+
+  rule comparator() -> Range
+    = o:op() _ v:version() {? with_op(&v, o) }
+
+  rule alternative() -> Range
+    = from:version() [' ' | '\t']+ "-" [' ' | '\t']+ to:version() {? hyphen(&from, &to) }
+    / c:(comparator() ** ([' ' | '\t']+)) { c.iter().fold(Range::any(), |range, c| range.intersect(c)) }
+
+  pub rule parse_range() -> RangeSet
+    = _ a:(alternative() ** (_ "||" _)) _ ![_] { RangeSet::new(a) }
+});
+
+// only what's needed to find the dependencies and where their strings are
+enum Json {
+  Object(Vec<(String, Json)>, Span),
+  String(String, Span),
+  Other(Span),
+}
+
+impl Json {
+  fn get(&self, key: &str) -> Option<&Json> {
+    match self {
+      Self::Object(entries, _) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+      _ => None,
+    }
+  }
+
+  fn span(&self) -> Span {
+    match self {
+      Self::Object(_, span) | Self::String(_, span) | Self::Other(span) => span.clone(),
+    }
+  }
+}
+
+peg::parser!( grammar json_parser() for str {
+  rule _() = [' ' | '\t' | '\n' | '\r']*
+
+  pub rule document() -> Json
+    = "\u{feff}"? _ v:value() _ ![_] { v }
+
+  rule value() -> Json
+    = s:position!() "{" _ m:(member() ** (_ "," _)) _ "}" e:position!() { Json::Object(m, s..e) }
+    / s:string() { Json::String(s.0, s.1) }
+    / s:position!() other() e:position!() { Json::Other(s..e) }
+
+  rule other()
+    = "[" _ (value() ** (_ "," _)) _ "]"
+    / "true" / "false" / "null"
+    / "-"? ['0'..='9']+ ("." ['0'..='9']+)? (['e' | 'E'] ['+' | '-']? ['0'..='9']+)?
+
+  rule member() -> (String, Json)
+    = k:string() _ ":" _ v:value() { (k.0, v) }
+
+  rule string() -> (String, Span)
+    = "\"" s:position!() c:character()* e:position!() "\"" { (c.into_iter().collect(), s..e) }
+
+  rule character() -> char
+    = "\\" e:escape() { e }
+    / c:[^ '"' | '\\'] { c }
+
+  rule escape() -> char
+    = "\"" { '"' } / "\\" { '\\' } / "/" { '/' } / "b" { '\u{8}' } / "f" { '\u{c}' } / "n" { '\n' } / "r" { '\r' } / "t" { '\t' }
+    // half of a surrogate pair, it's never a spec so it doesn't matter
+    / "u" h:$(['0'..='9' | 'a'..='f' | 'A'..='F']*<4>) { char::from_u32(u32::from_str_radix(h, 16).unwrap()).unwrap_or('\u{fffd}') }
+});
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use crate::Dependency;
+use crate::implementations::{Op, Range, Version};
+use crate::resolve::Provider;
+use crate::scheme::{SemVer, VersionScheme};
+
+// how big an upgrade is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Change {
+  // same major.minor.patch, another extra version or pre-release
+  PreRelease,
+  Patch,
+  Minor,
+  Major,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upgrade {
+  pub version: Version,
+  pub change: Change,
+}
+
+/// Where a package could go from its current version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outdated {
+  pub name: String,
+  pub current: Version,
+  pub range: Range,
+  // the newest version in range, what an update gives without touching the manifest
+  pub wanted: Option<Upgrade>,
+  // the newest version in ^current, what should upgrade without breaking anything
+  pub compatible: Option<Upgrade>,
+  pub latest: Option<Upgrade>,
+}
+
+impl Change {
+  /// None if to isn't newer than from
+  pub fn between(from: &Version, to: &Version) -> Option<Self> {
+    if !SemVer.cmp(to, from).is_gt() {
+      None
+    } else if to.major != from.major {
+      Some(Self::Major)
+    } else if to.minor != from.minor {
+      Some(Self::Minor)
+    } else if to.patch != from.patch {
+      Some(Self::Patch)
+    } else {
+      Some(Self::PreRelease)
+    }
+  }
+}
+
+impl Display for Change {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::PreRelease => "pre-release",
+      Self::Patch => "patch",
+      Self::Minor => "minor",
+      Self::Major => "major",
+    })
+  }
+}
+
+impl Outdated {
+  /// Whether any of the upgrades exists
+  pub fn is_outdated(&self) -> bool {
+    self.wanted.is_some() || self.compatible.is_some() || self.latest.is_some()
+  }
+}
+
+impl Display for Outdated {
+  // foo 1.1.0 (^1.0.0): wanted 1.4.0 (minor), compatible 1.9.0 (minor), latest 2.1.0 (major)
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} {} ({})", self.name, self.current, self.range)?;
+    let upgrades: Vec<String> = [("wanted", &self.wanted), ("compatible", &self.compatible), ("latest", &self.latest)].iter()
+      .filter_map(|(what, upgrade)| upgrade.as_ref().map(|u| format!("{} {} ({})", what, u.version, u.change)))
+      .collect();
+    match upgrades.is_empty() {
+      true => write!(f, ": up to date"),
+      false => write!(f, ": {}", upgrades.join(", ")),
+    }
+  }
+}
+
+// the newest version that's in range and newer than current
+fn newest(current: &Version, versions: &[Version], range: &Range) -> Option<Upgrade> {
+  versions.iter()
+    .filter(|v| range.contains((*v).clone()))
+    .max_by(|a, b| SemVer.cmp(a, b))
+    .and_then(|v| Change::between(current, v).map(|change| Upgrade { version: v.clone(), change }))
+}
+
+/// Like npm outdated: for every dependency that has a current version, the newest version in its range,
+/// in ^current and overall. Pre-releases are only considered when the current version is one.
+pub fn outdated<P: Provider>(current: &BTreeMap<String, Version>, dependencies: &[Dependency], provider: &P) -> Vec<Outdated> {
+  dependencies.iter().filter_map(|dependency| {
+    let current = current.get(&dependency.name)?;
+    let versions: Vec<Version> = provider.versions(&dependency.name).into_iter()
+      .filter(|v| v.pre_release.is_none() || current.pre_release.is_some())
+      .collect();
+    let caret = Range::from_ver_vec(vec![(Op::Caret, current.clone())]);
+    Some(Outdated {
+      name: dependency.name.clone(),
+      current: current.clone(),
+      range: dependency.range.clone(),
+      wanted: newest(current, &versions, &dependency.range),
+      compatible: newest(current, &versions, &caret),
+      latest: newest(current, &versions, &Range::any()),
+    })
+  }).collect()
+}
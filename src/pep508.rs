@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::{Dependency, ParseError};
+use crate::implementations::{Op, Range, Version};
+
+// requests[security,socks] >= 2.8.1, == 2.8.* ; python_version < "3.8"
+// https://peps.python.org/pep-0508/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+  pub dependency: Dependency,
+  pub extras: Vec<String>,
+  // name @ https://... instead of a version specifier
+  pub url: Option<String>,
+  pub marker: Option<Marker>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marker {
+  And(Box<Marker>, Box<Marker>),
+  Or(Box<Marker>, Box<Marker>),
+  Compare(MarkerValue, MarkerOp, MarkerValue),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerValue {
+  Variable(String), // python_version
+  Literal(String),  // "3.8"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarkerOp {
+  Eq,         // ==
+  Ne,         // !=
+  Lt,         // <
+  Le,         // <=
+  Gt,         // >
+  Ge,         // >=
+  Compatible, // ~=
+  Arbitrary,  // ===
+  In,         // in
+  NotIn,      // not in
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MarkerError {
+  #[error("marker variable {0} isn't in the environment")]
+  UndefinedVariable(String),
+}
+
+impl Requirement {
+  pub fn parse<S: Into<String>>(requirement: S) -> Result<Self, ParseError> {
+    pep508_parser::parse_requirement(&requirement.into())
+  }
+
+  /// Whether the requirement applies in this environment, true if there's no marker
+  pub fn applies(&self, environment: &HashMap<String, String>) -> Result<bool, MarkerError> {
+    match &self.marker {
+      None => Ok(true),
+      Some(marker) => marker.evaluate(environment),
+    }
+  }
+}
+
+impl FromStr for Requirement {
+  type Err = ParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+impl Marker {
+  pub fn parse<S: Into<String>>(marker: S) -> Result<Self, ParseError> {
+    pep508_parser::parse_marker(&marker.into())
+  }
+
+  /// Evaluates the marker with the variables in environment (python_version, sys_platform, extra, ...)
+  /// Comparisons are done as versions when both sides are versions, and as strings otherwise
+  pub fn evaluate(&self, environment: &HashMap<String, String>) -> Result<bool, MarkerError> {
+    match self {
+      Marker::And(a, b) => Ok(a.evaluate(environment)? && b.evaluate(environment)?),
+      Marker::Or(a, b) => Ok(a.evaluate(environment)? || b.evaluate(environment)?),
+      Marker::Compare(left, op, right) => {
+        let value = |v: &MarkerValue| match v {
+          MarkerValue::Literal(l) => Ok(l.clone()),
+          MarkerValue::Variable(name) => environment.get(name).cloned()
+            .ok_or_else(|| MarkerError::UndefinedVariable(name.clone())),
+        };
+        Ok(compare(&value(left)?, *op, &value(right)?))
+      }
+    }
+  }
+}
+
+impl FromStr for Marker {
+  type Err = ParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+fn compare(left: &str, op: MarkerOp, right: &str) -> bool {
+  match op {
+    MarkerOp::In => return right.contains(left),
+    MarkerOp::NotIn => return !right.contains(left),
+    MarkerOp::Arbitrary => return left == right,
+    _ => {}
+  }
+  // version comparison if possible, python_version < "3.8"
+  if let (Ok(version), Ok(range)) = (parse_version(left), pep508_parser::specifier(&format!("{}{}", op, right))) {
+    return range.contains(version);
+  }
+  match op {
+    MarkerOp::Eq => left == right,
+    MarkerOp::Ne => left != right,
+    MarkerOp::Lt => left < right,
+    MarkerOp::Le => left <= right,
+    MarkerOp::Gt => left > right,
+    MarkerOp::Ge => left >= right,
+    _ => false,
+  }
+}
+
+/// Reads a PEP 440 version into a Version, 1.0rc1 -> 1.0.0-rc1, 1.0.post1+local -> 1.0.0+post1.local.
+/// The post-release is part of the build, which Range only compares for == and !=, so >=1.0.post1 also matches 1.0.
+/// Epochs other than 0 (1!2.0) fail, a Version can't be ordered after every version without one
+pub fn parse_version<S: Into<String>>(version: S) -> Result<Version, ParseError> {
+  pep508_parser::pep440_version(version.into().trim())
+}
+
+/// Reads comma joined version specifiers, ">= 2.8.1, == 2.8.*" -> >=2.8.1 <2.9.0
+pub fn parse_specifiers<S: Into<String>>(specifiers: S) -> Result<Range, ParseError> {
+  pep508_parser::specifiers(&specifiers.into())
+}
+
+/// Reads a Poetry constraint: PEP 440 specifiers, ^1.2 (>=1.2 <2), ~1.2 (>=1.2 <1.3), bare versions (==1.2) and *
+pub fn parse_poetry_constraint<S: Into<String>>(constraint: S) -> Result<Range, ParseError> {
+  pep508_parser::poetry_constraint(&constraint.into())
+}
+
+impl Display for MarkerOp {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::Eq => "==",
+      Self::Ne => "!=",
+      Self::Lt => "<",
+      Self::Le => "<=",
+      Self::Gt => ">",
+      Self::Ge => ">=",
+      Self::Compatible => "~=",
+      Self::Arbitrary => "===",
+      Self::In => "in",
+      Self::NotIn => "not in",
+    })
+  }
+}
+
+impl Display for MarkerValue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Variable(name) => write!(f, "{}", name),
+      Self::Literal(value) if value.contains('"') => write!(f, "'{}'", value),
+      Self::Literal(value) => write!(f, "\"{}\"", value),
+    }
+  }
+}
+
+impl Display for Marker {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Marker::Compare(left, op, right) => write!(f, "{} {} {}", left, op, right),
+      Marker::Or(a, b) => write!(f, "{} or {}", a, b),
+      Marker::And(a, b) => {
+        // or binds weaker than and, so it needs parenthesis here
+        let side = |m: &Marker| match m {
+          Marker::Or(..) => format!("({})", m),
+          _ => m.to_string(),
+        };
+        write!(f, "{} and {}", side(a), side(b))
+      }
+    }
+  }
+}
+
+// the release numbers of a specifier version, and whether it ended in .*
+struct Specified {
+  version: Version,
+  release: Vec<u32>,
+  wildcard: bool,
+}
+
+// 1.2.3 -> 1.3.0 when keep is 2
+fn bump(release: &[u32], keep: usize) -> Version {
+  let mut numbers: Vec<u32> = release.iter().copied().take(keep).collect();
+  numbers.resize(keep.max(3), 0);
+  numbers[keep - 1] += 1;
+  let extra = if numbers.len() > 3 { Some(numbers[3..].iter().map(|n| n.to_string()).collect::<String>()) } else { None };
+  // only digits, can't fail
+  Version::new_w_extra(numbers[0], numbers[1], numbers[2], extra, None, None).unwrap()
+}
+
+fn specifier_range(op: &str, specified: Specified) -> Result<Range, &'static str> {
+  let Specified { version, release, wildcard } = specified;
+  let vec = match (op, wildcard) {
+    // == 2.8.* -> >=2.8 <2.9
+    ("==", true) => vec![(Op::Ge, version), (Op::Lt, bump(&release, release.len()))],
+    (_, true) => return Err("wildcards only work with == (!= 1.2.* can't be a Range)"),
+    // ~= 1.4.5 -> >=1.4.5 <1.5, ~= 2.2 -> >=2.2 <3
+    ("~=", false) if release.len() < 2 => return Err("~= needs at least two numbers"),
+    ("~=", false) => vec![(Op::Ge, version), (Op::Lt, bump(&release, release.len() - 1))],
+    ("===", false) => vec![(Op::Eq, version)],
+    (op, false) => vec![(Op::from_str(op).ok_or("operator")?, version)],
+  };
+  Ok(Range::from_ver_vec(vec))
+}
+
+// Poetry's ^ and ~ keep as many numbers as Cargo's and npm's: ^0.3 -> <0.4, ^1.2 -> <2, ~1.2.3 -> <1.3, ~1 -> <2
+fn poetry_range(op: &str, specified: Specified) -> Result<Range, &'static str> {
+  let Specified { version, release, wildcard } = specified;
+  if wildcard {
+    return Err("wildcards only work with ==");
+  }
+  let keep = match op {
+    "^" => release.iter().position(|n| *n != 0).map_or(release.len(), |i| i + 1),
+    _ => release.len().min(2),
+  };
+  Ok(Range::from_ver_vec(vec![(Op::Ge, version), (Op::Lt, bump(&release, keep))]))
+}
+
+fn and_ranges(ranges: Vec<Range>) -> Range {
+  ranges.into_iter().reduce(|a, b| a.intersect(&b)).unwrap_or_default()
+}
+
+peg::parser!( grammar pep508_parser() for str {
+  rule _() = [' ' | '\t']*
+  rule __() = [' ' | '\t']+
+
+  rule identifier() -> String
+    = i:$((['a'..='z' | 'A'..='Z' | '0'..='9']+) ++ (['-' | '_' | '.']+)) { i.to_string() }
+
+  rule num() -> u32
+    = n:$(['0'..='9']+) {? n.parse().or(Err("number")) }
+
+  // pep 440, normalized into Version:
+  // 1.2.3.4 -> extra 4, a1/b1/rc1 and dev1 -> pre_release, post1 and +local -> build
+  // Version has no epoch, so only 0! is taken, and more than 4 release numbers (1.2.3.4.5) are rejected
+  rule sep() = ['-' | '_' | '.']
+  rule pre_label() -> &'static str
+    = ("alpha" / "a") { "a" } / ("beta" / "b") { "b" } / ("rc" / "c" / "preview" / "pre") { "rc" }
+  rule pre() -> String
+    = sep()? l:pre_label() sep()? n:num()? { format!("{}{}", l, n.unwrap_or(0)) }
+  rule post() -> String
+    = "-" n:num() { format!("post{}", n) }
+    / sep()? ("post" / "rev" / "r") sep()? n:num()? { format!("post{}", n.unwrap_or(0)) }
+  rule dev() -> String
+    = sep()? "dev" sep()? n:num()? { format!("dev{}", n.unwrap_or(0)) }
+  rule local() -> String
+    = "+" l:$((['a'..='z' | 'A'..='Z' | '0'..='9']+) ++ ['-' | '_' | '.']) { l.to_lowercase().replace(['-', '_'], ".") }
+
+  rule release() -> (Vec<u32>, Version)
+    = ['v' | 'V']? e:(e:num() "!" { e })? r:(num() ++ ".") p:pre()? po:post()? d:dev()? l:local()? {?
+      if e.is_some_and(|e| e != 0) {
+        return Err("epoch 0, other epochs can't be ordered as a Version");
+      }
+      if r.len() > 4 {
+        return Err("at most 4 release numbers");
+      }
+      let number = |i: usize| r.get(i).copied().unwrap_or(0);
+      let extra = if r.len() > 3 { Some(r[3..].iter().map(|n| n.to_string()).collect::<String>()) } else { None };
+      let pre = match (p, d) {
+        (None, None) => None,
+        (p, d) => Some(format!("{}{}", p.unwrap_or_default(), d.unwrap_or_default())),
+      };
+      let build = match (po, l) {
+        (None, None) => None,
+        (po, l) => Some([po, l].into_iter().flatten().collect::<Vec<_>>().join(".")),
+      };
+      Version::new_w_extra(number(0), number(1), number(2), extra, pre, build)
+        .map(|v| (r.clone(), v))
+        .or(Err("version"))
+    }
+
+  pub rule pep440_version() -> Version
+    = v:release() ![_] { v.1 }
+
+  rule specified() -> Specified
+    = r:release() w:(".*")? { Specified { version: r.1, release: r.0, wildcard: w.is_some() } }
+
+  rule spec_op() -> &'input str
+    = $("===" / "==" / "!=" / "~=" / "<=" / ">=" / "<" / ">")
+
+  rule spec() -> Range
+    = o:spec_op() _ s:specified() {? specifier_range(o, s) }
+
+  pub rule specifier() -> Range
+    = _ s:spec() _ ![_] { s }
+
+  rule specs() -> Range
+    = "(" _ s:(spec() ++ (_ "," _)) _ ")" { and_ranges(s) }
+    / s:(spec() ++ (_ "," _)) { and_ranges(s) }
+
+  pub rule specifiers() -> Range
+    = _ s:specs() _ ![_] { s }
+
+  rule poetry() -> Range
+    = "*" { Range::any() }
+    / o:$("^" / "~" !"=") _ s:specified() {? poetry_range(o, s) }
+    / "=" !"=" _ s:specified() {? specifier_range("==", s) }
+    / s:specified() {? specifier_range("==", s) }
+    / spec()
+
+  pub rule poetry_constraint() -> Range
+    = _ c:(poetry() ++ ((_ "," _) / __)) _ ![_] { and_ranges(c) }
+
+  rule extras() -> Vec<String>
+    = "[" _ e:(identifier() ** (_ "," _)) _ "]" { e }
+
+  rule url() -> String
+    = "@" _ u:$([^ ' ' | '\t' | ';']+) { u.to_string() }
+
+  // markers
+  rule variable() -> MarkerValue
+    = v:$(['a'..='z' | '_']+) { MarkerValue::Variable(v.to_string()) }
+  rule literal() -> MarkerValue
+    = "\"" l:$([^ '"']*) "\"" { MarkerValue::Literal(l.to_string()) }
+    / "'" l:$([^ '\'']*) "'" { MarkerValue::Literal(l.to_string()) }
+  rule marker_value() -> MarkerValue
+    = literal() / variable()
+  rule marker_op() -> MarkerOp
+    = "===" { MarkerOp::Arbitrary } / "==" { MarkerOp::Eq } / "!=" { MarkerOp::Ne } / "~=" { MarkerOp::Compatible }
+    / "<=" { MarkerOp::Le } / ">=" { MarkerOp::Ge } / "<" { MarkerOp::Lt } / ">" { MarkerOp::Gt }
+    / "not" __ "in" { MarkerOp::NotIn } / "in" { MarkerOp::In }
+
+  rule marker_atom() -> Marker
+    = "(" _ m:marker_or() _ ")" { m }
+    / l:marker_value() _ o:marker_op() _ r:marker_value() { Marker::Compare(l, o, r) }
+  rule marker_and() -> Marker
+    = m:(marker_atom() ++ (_ "and" _)) { m.into_iter().reduce(|a, b| Marker::And(Box::new(a), Box::new(b))).unwrap() }
+  rule marker_or() -> Marker
+    = m:(marker_and() ++ (_ "or" _)) { m.into_iter().reduce(|a, b| Marker::Or(Box::new(a), Box::new(b))).unwrap() }
+
+  pub rule parse_marker() -> Marker
+    = _ m:marker_or() _ ![_] { m }
+
+  pub rule parse_requirement() -> Requirement
+    = _ n:identifier() _ e:extras()? _ v:(u:url() { (None, Some(u)) } / s:specs() { (Some(s), None) })? _ m:(";" _ m:marker_or() { m })? _ ![_] {
+      let (range, url) = v.unwrap_or((None, None));
+      Requirement {
+        dependency: Dependency { name: n, range: range.unwrap_or_default() },
+        extras: e.unwrap_or_default(),
+        url,
+        marker: m,
+      }
+    }
+});
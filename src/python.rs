@@ -0,0 +1,202 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use crate::Dependency;
+use crate::pep508::Requirement;
+#[cfg(feature = "toml")]
+use crate::lossless::Span;
+#[cfg(feature = "toml")]
+use crate::pep508::{parse_poetry_constraint, Marker};
+
+/// A line that couldn't be read, with the file it's in
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{}:{line}: {message}: {content}", .file.display())]
+pub struct LineError {
+  pub file: PathBuf,
+  // starting at 1, the first one of a line continued with \
+  pub line: usize,
+  pub content: String,
+  pub message: String,
+}
+
+/// The requirements that could be read, and the lines that couldn't
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Extracted {
+  pub requirements: Vec<Requirement>,
+  pub errors: Vec<LineError>,
+}
+
+impl Extracted {
+  pub fn dependencies(&self) -> Vec<Dependency> {
+    self.requirements.iter().map(|r| r.dependency.clone()).collect()
+  }
+}
+
+fn line_error(file: &Path, line: usize, content: &str, message: impl ToString) -> LineError {
+  LineError { file: file.to_path_buf(), line, content: content.to_string(), message: message.to_string() }
+}
+
+/// read_requirements_txt on the file at path, which can't be missing
+pub fn read_requirements_txt<P: AsRef<Path>>(path: P) -> io::Result<Extracted> {
+  let path = path.as_ref();
+  let text = fs::read_to_string(path)?;
+  Ok(parse_requirements_txt(&text, path))
+}
+
+/// The requirements of a pip requirements file, and the ones of the files it includes with -r (relative to file).
+/// Comments, options (-i, -c, --hash, ...), editable installs and bare paths or urls are skipped.
+pub fn parse_requirements_txt<P: AsRef<Path>>(text: &str, file: P) -> Extracted {
+  let mut extracted = Extracted::default();
+  let mut visited = vec![];
+  requirements_txt(text, file.as_ref(), &mut visited, &mut extracted);
+  extracted
+}
+
+// -r base.txt, -rbase.txt, --requirement=base.txt, --requirement base.txt
+fn include(line: &str) -> Option<&str> {
+  let rest = line.strip_prefix("--requirement").or_else(|| line.strip_prefix("-r"))?;
+  Some(rest.trim_start_matches([' ', '\t', '=']).trim())
+}
+
+fn requirements_txt(text: &str, file: &Path, visited: &mut Vec<PathBuf>, extracted: &mut Extracted) {
+  visited.push(fs::canonicalize(file).unwrap_or(file.to_path_buf()));
+  let mut lines = text.lines().enumerate();
+  while let Some((i, first)) = lines.next() {
+    // lines ending with \ go on in the next one
+    let mut line = first.to_string();
+    while line.ends_with('\\') {
+      line.pop();
+      match lines.next() {
+        Some((_, next)) => line.push_str(next),
+        None => break,
+      }
+    }
+    // comments start a line or come after whitespace, pkg#egg isn't one
+    let comment = line.match_indices('#').find(|(at, _)| *at == 0 || line[..*at].ends_with([' ', '\t'])).map(|(at, _)| at);
+    let line = line[..comment.unwrap_or(line.len())].trim();
+    if line.is_empty() {
+      continue;
+    }
+    if let Some(included) = include(line) {
+      let path = file.parent().unwrap_or(Path::new("")).join(included);
+      if visited.contains(&fs::canonicalize(&path).unwrap_or(path.clone())) {
+        continue;
+      }
+      match fs::read_to_string(&path) {
+        Ok(text) => requirements_txt(&text, &path, visited, extracted),
+        Err(e) => extracted.errors.push(line_error(file, i + 1, line, format!("can't read {}: {}", path.display(), e))),
+      }
+      continue;
+    }
+    let is_path = line.starts_with(['.', '/']) || (line.contains("://") && !line.contains('@'));
+    if line.starts_with('-') || is_path {
+      continue;
+    }
+    // foo==1.0 --hash=sha256:...
+    let requirement = line.find(" --").or_else(|| line.find("\t--")).map_or(line, |at| line[..at].trim_end());
+    match Requirement::parse(requirement) {
+      Ok(requirement) => extracted.requirements.push(requirement),
+      Err(e) => extracted.errors.push(line_error(file, i + 1, line, format!("invalid requirement, {}", e))),
+    }
+  }
+}
+
+/// parse_pyproject on the file at path, which can't be missing
+#[cfg(feature = "toml")]
+pub fn read_pyproject<P: AsRef<Path>>(path: P) -> io::Result<Extracted> {
+  let path = path.as_ref();
+  let text = fs::read_to_string(path)?;
+  Ok(parse_pyproject(&text, path))
+}
+
+/// The requirements of a pyproject.toml: PEP 621's [project] dependencies and optional-dependencies,
+/// and Poetry's tool.poetry.dependencies, dev-dependencies and group.*.dependencies ("^1.2", { version = "~1.2", extras = [...] }).
+/// The python requirement and dependencies without a version (git, path, url) are skipped.
+#[cfg(feature = "toml")]
+pub fn parse_pyproject<P: AsRef<Path>>(text: &str, file: P) -> Extracted {
+  use toml_edit::{ImDocument, Item, TableLike};
+  let file = file.as_ref();
+  let mut extracted = Extracted::default();
+  let document = match ImDocument::parse(text) {
+    Ok(document) => document,
+    Err(e) => {
+      let line = line_at(text, e.span().map_or(0, |span| span.start));
+      extracted.errors.push(line_error(file, line, text.lines().nth(line - 1).unwrap_or(""), e.message()));
+      return extracted;
+    }
+  };
+  let error = |at: Option<Span>, content: &str, message: String| {
+    line_error(file, line_at(text, at.map_or(0, |span| span.start)), content, message)
+  };
+  fn table<'a>(item: Option<&'a Item>, key: &str) -> Option<&'a Item> {
+    item.and_then(Item::as_table_like).and_then(|t| t.get(key))
+  }
+
+  // PEP 508 strings
+  let project = document.get("project");
+  let mut arrays = vec![table(project, "dependencies")];
+  if let Some(optional) = table(project, "optional-dependencies").and_then(Item::as_table_like) {
+    arrays.extend(optional.iter().map(|(_, item)| Some(item)));
+  }
+  for value in arrays.into_iter().flatten().filter_map(Item::as_array).flatten() {
+    let Some(requirement) = value.as_str() else { continue };
+    match Requirement::parse(requirement) {
+      Ok(requirement) => extracted.requirements.push(requirement),
+      Err(e) => extracted.errors.push(error(value.span(), requirement, format!("invalid requirement, {}", e))),
+    }
+  }
+
+  // Poetry's name = constraint tables
+  let poetry = table(document.get("tool"), "poetry");
+  let mut tables = vec![table(poetry, "dependencies"), table(poetry, "dev-dependencies")];
+  if let Some(groups) = table(poetry, "group").and_then(Item::as_table_like) {
+    tables.extend(groups.iter().map(|(_, group)| table(Some(group), "dependencies")));
+  }
+  for (name, item) in tables.into_iter().flatten().filter_map(Item::as_table_like).flat_map(|t| t.iter()) {
+    if name == "python" {
+      continue;
+    }
+    // foo = "^1.2", foo = { version = "^1.2" } or one of those per python version: foo = [{ version = "^1.2", python = "<3.8" }, ...]
+    let entries: Vec<PoetryEntry> = match item {
+      Item::Value(toml_edit::Value::Array(array)) => array.iter()
+        .filter_map(|v| v.as_inline_table())
+        .map(|t| (Some(t as &dyn TableLike), t.get("version").and_then(|v| v.as_str()), t.span()))
+        .collect(),
+      item => match item.as_table_like() {
+        Some(t) => vec![(Some(t), t.get("version").and_then(Item::as_str), item.span())],
+        None => vec![(None, item.as_str(), item.span())],
+      },
+    };
+    for (details, constraint, span) in entries {
+      let Some(constraint) = constraint else { continue };
+      let range = match parse_poetry_constraint(constraint) {
+        Ok(range) => range,
+        Err(e) => {
+          extracted.errors.push(error(span, constraint, format!("invalid constraint for {}, {}", name, e)));
+          continue;
+        }
+      };
+      let extras = details.and_then(|t| t.get("extras")).and_then(Item::as_array)
+        .map_or(vec![], |extras| extras.iter().filter_map(|e| e.as_str()).map(|e| e.to_string()).collect());
+      let marker = match details.and_then(|t| t.get("markers")).and_then(Item::as_str).map(Marker::parse) {
+        Some(Err(e)) => {
+          extracted.errors.push(error(span, constraint, format!("invalid markers for {}, {}", name, e)));
+          continue;
+        }
+        marker => marker.and_then(Result::ok),
+      };
+      extracted.requirements.push(Requirement { dependency: Dependency { name: name.to_string(), range }, extras, url: None, marker });
+    }
+  }
+  extracted
+}
+
+// the details table if there's one, the constraint and where it is
+#[cfg(feature = "toml")]
+type PoetryEntry<'a> = (Option<&'a dyn toml_edit::TableLike>, Option<&'a str>, Option<Span>);
+
+#[cfg(feature = "toml")]
+fn line_at(text: &str, at: usize) -> usize {
+  text[..at.min(text.len())].matches('\n').count() + 1
+}
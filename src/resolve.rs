@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+use crate::Dependency;
+use crate::implementations::{Range, Version};
+use crate::scheme::{SemVer, VersionScheme};
+
+/// Where the resolver gets the available versions of each package and their dependencies from
+pub trait Provider {
+  /// Every version of package, in any order
+  fn versions(&self, package: &str) -> Vec<Version>;
+  fn dependencies(&self, package: &str, version: &Version) -> Vec<Dependency>;
+}
+
+/// A Provider with everything in memory, mostly for tests and small tools
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+  packages: BTreeMap<String, Vec<(Version, Vec<Dependency>)>>,
+}
+
+impl MemoryProvider {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add<S: Into<String>>(&mut self, package: S, version: Version, dependencies: Vec<Dependency>) -> &mut Self {
+    self.packages.entry(package.into()).or_default().push((version, dependencies));
+    self
+  }
+}
+
+impl Provider for MemoryProvider {
+  fn versions(&self, package: &str) -> Vec<Version> {
+    self.packages.get(package).map_or(vec![], |versions| versions.iter().map(|(v, _)| v.clone()).collect())
+  }
+
+  fn dependencies(&self, package: &str, version: &Version) -> Vec<Dependency> {
+    self.packages.get(package)
+      .and_then(|versions| versions.iter().find(|(v, _)| v.is(version)))
+      .map_or(vec![], |(_, dependencies)| dependencies.clone())
+  }
+}
+
+// who asked for a range of a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Requirer {
+  Root,
+  Package(String, Version),
+}
+
+impl Display for Requirer {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Root => write!(f, "root"),
+      Self::Package(name, version) => write!(f, "{} {}", name, version),
+    }
+  }
+}
+
+/// Why there's no solution, one sentence per step from the first conflict found to the root requirements
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{}", .explanation.join("\n"))]
+pub struct Conflict {
+  pub explanation: Vec<String>,
+  // the packages whose picked versions led to this, going back to any other of them could fix it
+  involved: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+  decisions: BTreeMap<String, Version>,
+  requirements: BTreeMap<String, Vec<(Requirer, Range)>>,
+}
+
+impl State {
+  fn range(&self, package: &str) -> Range {
+    self.requirements.get(package).map_or(Range::any(), |requirements| {
+      requirements.iter().fold(Range::any(), |range, (_, r)| range.intersect(r))
+    })
+  }
+
+  fn requirers(&self, package: &str) -> BTreeSet<String> {
+    self.requirements.get(package).map_or(BTreeSet::new(), |requirements| {
+      requirements.iter().filter_map(|(requirer, _)| match requirer {
+        Requirer::Root => None,
+        Requirer::Package(name, _) => Some(name.clone()),
+      }).collect()
+    })
+  }
+
+  // adds a requirement, failing when it can't be met together with what's already there
+  fn require(&mut self, requirer: Requirer, dependency: &Dependency) -> Result<(), Conflict> {
+    let package = &dependency.name;
+    let mut involved = self.requirers(package);
+    if let Requirer::Package(name, _) = &requirer {
+      involved.insert(name.clone());
+    }
+    if self.range(package).intersect(&dependency.range).is_empty() {
+      // the requirement that can't be met with the new one
+      let other = self.requirements.get(package).into_iter().flatten()
+        .find(|(_, r)| r.intersect(&dependency.range).is_empty())
+        .map_or_else(|| format!("the other requirements of {}", package), |(requirer, range)| format!("{} requires {} {}", requirer, package, range));
+      return Err(Conflict {
+        explanation: vec![format!("{} requires {} {} but {}", requirer, package, dependency.range, other)],
+        involved,
+      });
+    }
+    if let Some(picked) = self.decisions.get(package) {
+      if !dependency.range.contains(picked.clone()) {
+        involved.insert(package.clone());
+        return Err(Conflict {
+          explanation: vec![format!("{} requires {} {} but {} {} was picked ({})", requirer, package, dependency.range, package, picked, self.required_by(package))],
+          involved,
+        });
+      }
+    }
+    self.requirements.entry(package.clone()).or_default().push((requirer, dependency.range.clone()));
+    Ok(())
+  }
+
+  // "root requires foo ^1.0.0 and bar 1.0.0 requires foo <1.5.0"
+  fn required_by(&self, package: &str) -> String {
+    self.requirements.get(package).into_iter().flatten()
+      .map(|(requirer, range)| format!("{} requires {} {}", requirer, package, range))
+      .collect::<Vec<_>>()
+      .join(" and ")
+  }
+}
+
+// the next package to decide, the one with the fewest versions left like PubGrub does
+fn next<P: Provider>(state: &State, provider: &P) -> Option<(String, Vec<Version>)> {
+  state.requirements.keys()
+    .filter(|package| !state.decisions.contains_key(*package))
+    .map(|package| {
+      let range = state.range(package);
+      let mut versions: Vec<Version> = provider.versions(package).into_iter().filter(|v| range.contains(v.clone())).collect();
+      // newest first
+      versions.sort_by(|a, b| SemVer.cmp(b, a));
+      (package.clone(), versions)
+    })
+    .min_by_key(|(_, versions)| versions.len())
+}
+
+fn solve<P: Provider>(state: State, provider: &P) -> Result<State, Conflict> {
+  let Some((package, versions)) = next(&state, provider) else { return Ok(state) };
+  let mut involved = state.requirers(&package);
+  if versions.is_empty() {
+    let available = provider.versions(&package);
+    let reason = if available.is_empty() {
+      format!("{} doesn't exist", package)
+    } else {
+      format!("{} but no version of {} matches", state.required_by(&package), package)
+    };
+    return Err(Conflict { explanation: vec![reason], involved });
+  }
+
+  let mut explanation = vec![];
+  for version in versions {
+    let mut next_state = state.clone();
+    next_state.decisions.insert(package.clone(), version.clone());
+    let requirer = Requirer::Package(package.clone(), version.clone());
+    let result = provider.dependencies(&package, &version).iter()
+      .try_for_each(|dependency| next_state.require(requirer.clone(), dependency))
+      .and_then(|_| solve(next_state, provider));
+    match result {
+      Ok(solved) => return Ok(solved),
+      // picking another version of package won't help, go back further
+      Err(conflict) if !conflict.involved.contains(&package) => return Err(conflict),
+      Err(conflict) => {
+        explanation.extend(conflict.explanation);
+        involved.extend(conflict.involved.into_iter().filter(|p| *p != package));
+      }
+    }
+  }
+  explanation.push(format!("so no version of {} fits, but {}", package, state.required_by(&package)));
+  Err(Conflict { explanation, involved })
+}
+
+/// Finds a version for every package reachable from the root dependencies so that every requirement is met,
+/// preferring newer versions and backtracking when something doesn't fit.
+/// If there's none, the Conflict explains why ("foo 2.0.0 requires bar <1.0.0 but root requires bar ^1.2.0").
+pub fn resolve<P: Provider>(root: &[Dependency], provider: &P) -> Result<BTreeMap<String, Version>, Conflict> {
+  let mut state = State::default();
+  for dependency in root {
+    state.require(Requirer::Root, dependency)?;
+  }
+  Ok(solve(state, provider)?.decisions)
+}
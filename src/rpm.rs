@@ -0,0 +1,357 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::Dependency;
+use crate::implementations::{Op, Range, Version};
+
+// [epoch:]version[-release]
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr))]
+pub struct Evr {
+  pub epoch: Option<u32>,
+  pub version: String,
+  pub release: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RpmError {
+  #[error("empty version string")]
+  Empty,
+  #[error("invalid epoch: {0}")]
+  InvalidEpoch(String),
+  #[error("empty version in {0}")]
+  EmptyVersion(String),
+  #[error("empty release in {0}")]
+  EmptyRelease(String),
+  #[error("invalid requirement: {0}")]
+  InvalidRequirement(String),
+  #[error("{0} can't be represented as a Range")]
+  Unrepresentable(String),
+}
+
+impl Evr {
+  pub fn new<S: Into<String>>(epoch: Option<u32>, version: S, release: Option<S>) -> Self {
+    Self {
+      epoch,
+      version: version.into(),
+      release: release.map(|s| s.into()),
+    }
+  }
+
+  pub fn parse<S: Into<String>>(evr: S) -> Result<Self, RpmError> {
+    let evr = evr.into();
+    let evr = evr.trim();
+    if evr.is_empty() {
+      return Err(RpmError::Empty);
+    }
+    let (epoch, rest) = match evr.split_once(':') {
+      Some((e, rest)) => (Some(e.parse().map_err(|_| RpmError::InvalidEpoch(e.to_string()))?), rest),
+      None => (None, evr),
+    };
+    let (version, release) = match rest.rsplit_once('-') {
+      Some((v, r)) => (v, Some(r)),
+      None => (rest, None),
+    };
+    if version.is_empty() {
+      return Err(RpmError::EmptyVersion(evr.to_string()));
+    }
+    if release == Some("") {
+      return Err(RpmError::EmptyRelease(evr.to_string()));
+    }
+    Ok(Self::new(epoch, version, release))
+  }
+
+  /// Comparison used for dependencies, where a side without release matches any release
+  /// (Requires: foo = 1.2 is satisfied by foo-1.2-3)
+  pub fn cmp_dep(&self, other: &Self) -> Ordering {
+    let ord = self.epoch.unwrap_or(0).cmp(&other.epoch.unwrap_or(0))
+      .then_with(|| rpmvercmp(&self.version, &other.version));
+    match (&self.release, &other.release) {
+      (Some(a), Some(b)) => ord.then_with(|| rpmvercmp(a, b)),
+      _ => ord,
+    }
+  }
+}
+
+/// Same algorithm as rpm's rpmvercmp, including ~ (sorts before anything, even the end)
+/// and ^ (sorts after the end but before anything else)
+pub fn rpmvercmp(a: &str, b: &str) -> Ordering {
+  if a == b {
+    return Ordering::Equal;
+  }
+  let (a, b) = (a.as_bytes(), b.as_bytes());
+  let (mut i, mut j) = (0, 0);
+  let separator = |c: u8| !c.is_ascii_alphanumeric() && c != b'~' && c != b'^';
+  while i < a.len() || j < b.len() {
+    while i < a.len() && separator(a[i]) {
+      i += 1;
+    }
+    while j < b.len() && separator(b[j]) {
+      j += 1;
+    }
+    let (one, two) = (a.get(i).copied(), b.get(j).copied());
+    if one == Some(b'~') || two == Some(b'~') {
+      if one != Some(b'~') {
+        return Ordering::Greater;
+      }
+      if two != Some(b'~') {
+        return Ordering::Less;
+      }
+      i += 1;
+      j += 1;
+      continue;
+    }
+    if one == Some(b'^') || two == Some(b'^') {
+      if one.is_none() {
+        return Ordering::Less;
+      }
+      if two.is_none() {
+        return Ordering::Greater;
+      }
+      if one != Some(b'^') {
+        return Ordering::Greater;
+      }
+      if two != Some(b'^') {
+        return Ordering::Less;
+      }
+      i += 1;
+      j += 1;
+      continue;
+    }
+    if one.is_none() || two.is_none() {
+      break;
+    }
+    // grab the next segment, numeric or alpha depending on what the left side starts with
+    let numeric = a[i].is_ascii_digit();
+    let segment = |s: &[u8], start: usize| {
+      let mut end = start;
+      while end < s.len() && if numeric { s[end].is_ascii_digit() } else { s[end].is_ascii_alphabetic() } {
+        end += 1;
+      }
+      end
+    };
+    let (end_one, end_two) = (segment(a, i), segment(b, j));
+    if end_two == j {
+      // different types, numeric is newer
+      return if numeric { Ordering::Greater } else { Ordering::Less };
+    }
+    let (mut seg_one, mut seg_two) = (&a[i..end_one], &b[j..end_two]);
+    if numeric {
+      while seg_one.first() == Some(&b'0') {
+        seg_one = &seg_one[1..];
+      }
+      while seg_two.first() == Some(&b'0') {
+        seg_two = &seg_two[1..];
+      }
+      // more digits wins
+      let ord = seg_one.len().cmp(&seg_two.len());
+      if ord != Ordering::Equal {
+        return ord;
+      }
+    }
+    let ord = seg_one.cmp(seg_two);
+    if ord != Ordering::Equal {
+      return ord;
+    }
+    i = end_one;
+    j = end_two;
+  }
+  match (i < a.len(), j < b.len()) {
+    (false, false) => Ordering::Equal,
+    (true, _) => Ordering::Greater,
+    (false, true) => Ordering::Less,
+  }
+}
+
+impl Ord for Evr {
+  // total order, a missing epoch is 0 and a missing release sorts before any release
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.epoch.unwrap_or(0).cmp(&other.epoch.unwrap_or(0))
+      .then_with(|| rpmvercmp(&self.version, &other.version))
+      .then_with(|| match (&self.release, &other.release) {
+        (Some(a), Some(b)) => rpmvercmp(a, b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+      })
+  }
+}
+impl PartialOrd for Evr {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl PartialEq for Evr {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Display for Evr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if let Some(epoch) = self.epoch {
+      write!(f, "{}:", epoch)?;
+    }
+    write!(f, "{}", self.version)?;
+    if let Some(release) = &self.release {
+      write!(f, "-{}", release)?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Evr {
+  type Err = RpmError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RpmOp {
+  Lt, // <
+  Le, // <=
+  Eq, // = and ==
+  Ge, // >=
+  Gt, // >
+}
+
+impl RpmOp {
+  pub fn from_symbol(op: &str) -> Option<Self> {
+    match op {
+      "<" => Some(Self::Lt),
+      "<=" | "=<" => Some(Self::Le),
+      "=" | "==" => Some(Self::Eq),
+      ">=" | "=>" => Some(Self::Ge),
+      ">" => Some(Self::Gt),
+      _ => None
+    }
+  }
+}
+
+impl Display for RpmOp {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::Lt => "<",
+      Self::Le => "<=",
+      Self::Eq => "=",
+      Self::Ge => ">=",
+      Self::Gt => ">",
+    })
+  }
+}
+
+// a single requirement, like "foo >= 1.2-3" or "perl(Foo::Bar)"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+  pub name: String,
+  pub constraint: Option<(RpmOp, Evr)>,
+}
+
+impl Requirement {
+  pub fn satisfied_by(&self, evr: &Evr) -> bool {
+    match &self.constraint {
+      None => true,
+      Some((op, wanted)) => {
+        let ord = evr.cmp_dep(wanted);
+        match op {
+          RpmOp::Lt => ord == Ordering::Less,
+          RpmOp::Le => ord != Ordering::Greater,
+          RpmOp::Eq => ord == Ordering::Equal,
+          RpmOp::Ge => ord != Ordering::Less,
+          RpmOp::Gt => ord == Ordering::Greater,
+        }
+      }
+    }
+  }
+
+  /// Maps the requirement into a Dependency with a Range.
+  /// The version is read leniently (1.2~rc1 -> 1.2.0-rc1) and the release is kept as the build,
+  /// since Range ignores builds that means foo >= 1.2-3 becomes >=1.2.0 (any release of 1.2).
+  /// Epochs can't be represented, so anything with a non zero epoch fails.
+  pub fn to_dependency(&self) -> Result<Dependency, RpmError> {
+    let range = match &self.constraint {
+      None => Range::any(),
+      Some((op, evr)) => {
+        let unrepresentable = || RpmError::Unrepresentable(self.to_string());
+        if evr.epoch.unwrap_or(0) != 0 || evr.version.contains('^') || evr.version.matches('~').count() > 1 {
+          return Err(unrepresentable());
+        }
+        let version = Version::parse(evr.version.replacen('~', "-", 1)).map_err(|_| unrepresentable())?
+          .with_build(evr.release.clone());
+        let op = match op {
+          RpmOp::Lt => Op::Lt,
+          RpmOp::Le => Op::Le,
+          RpmOp::Eq => Op::Eq,
+          RpmOp::Ge => Op::Ge,
+          RpmOp::Gt => Op::Gt,
+        };
+        Range::from_ver_vec(vec![(op, version)])
+      }
+    };
+    Ok(Dependency {
+      name: self.name.clone(),
+      range,
+    })
+  }
+}
+
+impl Display for Requirement {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name)?;
+    if let Some((op, evr)) = &self.constraint {
+      write!(f, " {} {}", op, evr)?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Requirement {
+  type Err = RpmError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match parse_requires(s)?.as_slice() {
+      [single] => Ok(single.clone()),
+      _ => Err(RpmError::InvalidRequirement(s.to_string())),
+    }
+  }
+}
+
+/// Parses a Requires/BuildRequires/Provides line (the tag is optional),
+/// "Requires: foo >= 1.2-3, bar, baz < 2" -> [foo >= 1.2-3, bar, baz < 2]
+pub fn parse_requires<S: Into<String>>(line: S) -> Result<Vec<Requirement>, RpmError> {
+  let line = line.into();
+  rpm_parser::requires(&line).map_err(|_| RpmError::InvalidRequirement(line.clone()))?
+    .into_iter()
+    .collect()
+}
+
+peg::parser!( grammar rpm_parser() for str {
+  rule _() = [' ' | '\t']*
+  rule __() = [' ' | '\t']+
+
+  rule tag()
+    = ['A'..='Z'] ['a'..='z' | 'A'..='Z']* ("(" [^ ')']* ")")? _ ":" _
+
+  // names can have parenthesis, like perl(Foo::Bar) or config(foo)
+  rule name() -> String
+    = n:$((['(' | ')' | '/' | ':' | '.' | '+' | '-' | '_' | '@' | 'a'..='z' | 'A'..='Z' | '0'..='9'])+) { n.to_string() }
+
+  rule op() -> RpmOp
+    = o:$("<=" / "=<" / ">=" / "=>" / "==" / "<" / ">" / "=") { RpmOp::from_symbol(o).unwrap() }
+
+  rule evr() -> &'input str
+    = $([^ ' ' | '\t' | ',']+)
+
+  rule requirement() -> Result<Requirement, RpmError>
+    = n:name() c:(_ o:op() _ e:evr() { (o, e) })? {
+      let constraint = match c {
+        Some((o, e)) => Some((o, Evr::parse(e)?)),
+        None => None,
+      };
+      Ok(Requirement { name: n, constraint })
+    }
+
+  pub rule requires() -> Vec<Result<Requirement, RpmError>>
+    = _ tag()? r:(requirement() ++ ((_ "," _) / __)) _ ![_] { r }
+});
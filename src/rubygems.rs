@@ -0,0 +1,190 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::ParseError;
+use crate::implementations::{Op, Range, Version};
+
+// A version as Gem::Version sees it, "1.2.0.rc1" -> [1, 2, 0, "rc", 1]
+// letters anywhere make it a prerelease
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr))]
+pub struct GemVersion {
+  pub segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+  Number(u64),
+  Letters(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GemError {
+  #[error("{0} can't be represented as a Version")]
+  Unrepresentable(String),
+}
+
+impl GemVersion {
+  pub fn parse<S: Into<String>>(version: S) -> Result<Self, ParseError> {
+    gem_parser::parse_version(&version.into())
+  }
+
+  pub fn is_prerelease(&self) -> bool {
+    self.segments.iter().any(|s| matches!(s, Segment::Letters(_)))
+  }
+
+  // release part and prerelease part without trailing zeros, like Gem::Version#canonical_segments
+  fn canonical_segments(&self) -> Vec<Segment> {
+    let split = self.segments.iter().position(|s| matches!(s, Segment::Letters(_))).unwrap_or(self.segments.len());
+    let (release, pre) = self.segments.split_at(split);
+    let trim = |part: &[Segment]| {
+      let mut part = part.to_vec();
+      while part.len() > 1 && part.last() == Some(&Segment::Number(0)) {
+        part.pop();
+      }
+      part
+    };
+    let mut canonical = trim(release);
+    canonical.extend(trim(pre));
+    canonical
+  }
+
+  /// Gem::Version#bump, the upper bound of ~>
+  /// 2.1 -> 3, 2.1.0 -> 2.2, 2 -> 3, 2.1.rc1 -> 3
+  pub fn bump(&self) -> Self {
+    let mut segments: Vec<Segment> = self.segments.iter()
+      .take_while(|s| matches!(s, Segment::Number(_)))
+      .cloned()
+      .collect();
+    if segments.len() > 1 {
+      segments.pop();
+    }
+    if let Some(Segment::Number(last)) = segments.last_mut() {
+      *last += 1;
+    }
+    Self { segments }
+  }
+
+  /// Converts to a Version, the first 3 numbers are major, minor and patch, a 4th is the extra_version
+  /// and everything from the first letters on is the pre_release (1.2.3.4.rc.1 -> 1.2.3.4-rc1).
+  /// The separators can't be kept, so versions where dropping them would change the version fail:
+  /// a 5th number (1.2.3.4.10 isn't 1.2.3.410), numbers or letters in a row after the first letters
+  /// (1.0.rc.1.2 isn't 1.0.rc12), and numbers that don't fit in a u32
+  pub fn to_version(&self) -> Result<Version, GemError> {
+    let unrepresentable = || GemError::Unrepresentable(self.to_string());
+    let split = self.segments.iter().position(|s| matches!(s, Segment::Letters(_))).unwrap_or(self.segments.len());
+    let (release, pre) = self.segments.split_at(split);
+    let alternating = pre.windows(2).all(|w| matches!(w, [Segment::Letters(_), Segment::Number(_)] | [Segment::Number(_), Segment::Letters(_)]));
+    if release.len() > 4 || !alternating {
+      return Err(unrepresentable());
+    }
+    let number = |i: usize| match release.get(i) {
+      Some(Segment::Number(n)) => u32::try_from(*n).map_err(|_| unrepresentable()),
+      _ => Ok(0),
+    };
+    let extra = release.get(3).map(|s| s.to_string());
+    let pre = if pre.is_empty() { None } else { Some(pre.iter().map(|s| s.to_string()).collect::<String>()) };
+    Version::new_w_extra(number(0)?, number(1)?, number(2)?, extra, pre, None).map_err(|_| unrepresentable())
+  }
+}
+
+impl Ord for GemVersion {
+  // Gem::Version#<=>, missing segments are 0 and letters are always older than numbers
+  fn cmp(&self, other: &Self) -> Ordering {
+    let (lhs, rhs) = (self.canonical_segments(), other.canonical_segments());
+    let zero = Segment::Number(0);
+    for i in 0..lhs.len().max(rhs.len()) {
+      let ord = match (lhs.get(i).unwrap_or(&zero), rhs.get(i).unwrap_or(&zero)) {
+        (Segment::Number(a), Segment::Number(b)) => a.cmp(b),
+        (Segment::Letters(a), Segment::Letters(b)) => a.cmp(b),
+        (Segment::Letters(_), Segment::Number(_)) => Ordering::Less,
+        (Segment::Number(_), Segment::Letters(_)) => Ordering::Greater,
+      };
+      if ord != Ordering::Equal {
+        return ord;
+      }
+    }
+    Ordering::Equal
+  }
+}
+impl PartialOrd for GemVersion {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl PartialEq for GemVersion {
+  // 1.0 == 1 == 1.0.0
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Display for Segment {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Segment::Number(n) => write!(f, "{}", n),
+      Segment::Letters(l) => write!(f, "{}", l),
+    }
+  }
+}
+impl Display for GemVersion {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let s = self.segments.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(".");
+    write!(f, "{}", s)
+  }
+}
+impl FromStr for GemVersion {
+  type Err = ParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+/// Parses a gem requirement ("~> 2.1", ">= 1.0, != 1.1.5, < 3") into a Range
+/// ~> 2.1 -> >=2.1.0 <3.0.0, ~> 2.1.0 -> >=2.1.0 <2.2.0
+pub fn parse_range<S: Into<String>>(range: S) -> Result<Range, ParseError> {
+  gem_parser::parse_range(&range.into())
+}
+
+fn pessimistic_to_vec(version: GemVersion) -> Result<Vec<(Op, Version)>, GemError> {
+  Ok(vec![
+    (Op::Ge, version.to_version()?),
+    (Op::Lt, version.bump().to_version()?),
+  ])
+}
+
+peg::parser!( grammar gem_parser() for str {
+  rule _() = [' ' | '\t']*
+
+  rule number() -> Segment
+    = n:$(['0'..='9']+) {? n.parse().map(Segment::Number).or(Err("number")) }
+  rule letters() -> Segment
+    = l:$(['a'..='z' | 'A'..='Z']+) { Segment::Letters(l.to_string()) }
+  // 1.0.0rc1 is the same as 1.0.0.rc.1
+  rule segment() -> Vec<Segment>
+    = s:(number() / letters())+ { s }
+
+  rule version() -> GemVersion
+    = s:(segment() ++ ".") { GemVersion { segments: s.into_iter().flatten().collect() } }
+
+  pub rule parse_version() -> GemVersion
+    = _ v:version() _ ![_] { v }
+
+  rule op() -> Option<Op>
+    = "~>" { None }
+    / o:$("!=" / ">=" / "<=" / "=" / ">" / "<" / "") { Some(Op::from_str(o).unwrap()) }
+
+  rule requirement() -> Range
+    = o:op() _ v:version() {?
+      match o {
+        Some(o) => v.to_version().map(|v| vec![(o, v)]),
+        None => pessimistic_to_vec(v),
+      }.map(Range::from_ver_vec).or(Err("a version that fits in a Version"))
+    }
+
+  // every requirement has to match: ~> 5.2, >= 5.2.4 is >=5.2.4 <6
+  pub rule parse_range() -> Range
+    = _ r:(requirement() ++ (_ "," _)) _ ![_] { r.iter().fold(Range::any(), |range, r| range.intersect(r)) }
+});
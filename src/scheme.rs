@@ -0,0 +1,257 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use crate::ParseError;
+use crate::calver::{CalVer, CalVerError, CalVerFormat};
+use crate::composer::Constraint;
+use crate::debian::{DebianError, DebianVersion, Relation};
+use crate::implementations::{from_radix, Range, Version};
+use crate::rpm::{Evr, RpmError};
+use crate::rubygems::GemVersion;
+use crate::{pep508, rpm, rubygems};
+
+/// A versioning scheme: how versions and requirements are read, ordered and matched.
+/// Lets tools be generic over SemVer, PEP 440, Debian, etc. instead of hard-coding Version.
+pub trait VersionScheme {
+  type Version: Display;
+  type Requirement;
+  type Error: std::error::Error;
+
+  fn parse(&self, version: &str) -> Result<Self::Version, Self::Error>;
+  fn cmp(&self, a: &Self::Version, b: &Self::Version) -> Ordering;
+  fn parse_req(&self, requirement: &str) -> Result<Self::Requirement, Self::Error>;
+  fn matches(&self, requirement: &Self::Requirement, version: &Self::Version) -> bool;
+
+  /// Oldest first
+  fn sort(&self, versions: &mut [Self::Version]) {
+    versions.sort_by(|a, b| self.cmp(a, b));
+  }
+
+  fn max_satisfying<'a>(&self, requirement: &Self::Requirement, versions: &'a [Self::Version]) -> Option<&'a Self::Version> {
+    versions.iter()
+      .filter(|v| self.matches(requirement, v))
+      .max_by(|a, b| self.cmp(a, b))
+  }
+}
+
+// Version's Ord ignores the pre-release (1.0.0-alpha is "equal" to 1.0.0 in a Range), but sorting shouldn't
+fn cmp_with_pre(a: &Version, b: &Version) -> Ordering {
+  a.cmp(b).then_with(|| match (&a.pre_release, &b.pre_release) {
+    (None, None) => Ordering::Equal,
+    (None, Some(_)) => Ordering::Greater,
+    (Some(_), None) => Ordering::Less,
+    (Some(a), Some(b)) => cmp_pre(&from_radix(a), &from_radix(b)),
+  })
+}
+
+// the dots are gone (rc.10 is rc10), so the digits and letters in a row are the identifiers:
+// digits compare as numbers (rc9 < rc10) and are older than letters, like SemVer's numeric identifiers
+fn cmp_pre(a: &str, b: &str) -> Ordering {
+  fn runs(s: &str) -> Vec<&str> {
+    let mut runs = vec![];
+    let mut start = 0;
+    for (i, c) in s.char_indices().skip(1) {
+      if c.is_ascii_digit() != s[start..].starts_with(|c: char| c.is_ascii_digit()) {
+        runs.push(&s[start..i]);
+        start = i;
+      }
+    }
+    runs.push(&s[start..]);
+    runs
+  }
+  let (a, b) = (runs(a), runs(b));
+  for (a, b) in a.iter().zip(&b) {
+    let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+      (Ok(a), Ok(b)) => a.cmp(&b),
+      (Ok(_), Err(_)) => Ordering::Less,
+      (Err(_), Ok(_)) => Ordering::Greater,
+      (Err(_), Err(_)) => a.cmp(b),
+    };
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+  // alpha < alpha1
+  a.len().cmp(&b.len())
+}
+
+/// Version and Range read with the lenient Parser, what the rest of the crate uses. This is the default scheme.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SemVer;
+
+impl VersionScheme for SemVer {
+  type Version = Version;
+  type Requirement = Range;
+  type Error = ParseError;
+
+  fn parse(&self, version: &str) -> Result<Version, ParseError> {
+    Version::parse(version)
+  }
+
+  fn cmp(&self, a: &Version, b: &Version) -> Ordering {
+    cmp_with_pre(a, b)
+  }
+
+  fn parse_req(&self, requirement: &str) -> Result<Range, ParseError> {
+    Range::parse(requirement)
+  }
+
+  fn matches(&self, requirement: &Range, version: &Version) -> bool {
+    requirement.contains(version.clone())
+  }
+}
+
+/// PEP 440 versions and specifiers (">= 2.8.1, == 2.8.*"), read into Version and Range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pep440;
+
+impl VersionScheme for Pep440 {
+  type Version = Version;
+  type Requirement = Range;
+  type Error = ParseError;
+
+  fn parse(&self, version: &str) -> Result<Version, ParseError> {
+    pep508::parse_version(version)
+  }
+
+  fn cmp(&self, a: &Version, b: &Version) -> Ordering {
+    cmp_with_pre(a, b)
+  }
+
+  fn parse_req(&self, requirement: &str) -> Result<Range, ParseError> {
+    pep508::parse_specifiers(requirement)
+  }
+
+  fn matches(&self, requirement: &Range, version: &Version) -> bool {
+    requirement.contains(version.clone())
+  }
+}
+
+/// Debian versions, requirements are single relations like "libc6 (>= 2.34)"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Debian;
+
+impl VersionScheme for Debian {
+  type Version = DebianVersion;
+  type Requirement = Relation;
+  type Error = DebianError;
+
+  fn parse(&self, version: &str) -> Result<DebianVersion, DebianError> {
+    DebianVersion::parse(version)
+  }
+
+  fn cmp(&self, a: &DebianVersion, b: &DebianVersion) -> Ordering {
+    a.cmp(b)
+  }
+
+  fn parse_req(&self, requirement: &str) -> Result<Relation, DebianError> {
+    Relation::parse(requirement)
+  }
+
+  fn matches(&self, requirement: &Relation, version: &DebianVersion) -> bool {
+    requirement.satisfied_by(version)
+  }
+}
+
+/// RPM epoch:version-release, requirements are single ones like "foo >= 1.2-3"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rpm;
+
+impl VersionScheme for Rpm {
+  type Version = Evr;
+  type Requirement = rpm::Requirement;
+  type Error = RpmError;
+
+  fn parse(&self, version: &str) -> Result<Evr, RpmError> {
+    Evr::parse(version)
+  }
+
+  fn cmp(&self, a: &Evr, b: &Evr) -> Ordering {
+    a.cmp(b)
+  }
+
+  fn parse_req(&self, requirement: &str) -> Result<rpm::Requirement, RpmError> {
+    requirement.parse()
+  }
+
+  fn matches(&self, requirement: &rpm::Requirement, version: &Evr) -> bool {
+    requirement.satisfied_by(version)
+  }
+}
+
+/// Gem::Version ordering, requirements like "~> 2.1, != 2.1.3"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RubyGems;
+
+impl VersionScheme for RubyGems {
+  type Version = GemVersion;
+  type Requirement = Range;
+  type Error = ParseError;
+
+  fn parse(&self, version: &str) -> Result<GemVersion, ParseError> {
+    GemVersion::parse(version)
+  }
+
+  fn cmp(&self, a: &GemVersion, b: &GemVersion) -> Ordering {
+    a.cmp(b)
+  }
+
+  fn parse_req(&self, requirement: &str) -> Result<Range, ParseError> {
+    rubygems::parse_range(requirement)
+  }
+
+  fn matches(&self, requirement: &Range, version: &GemVersion) -> bool {
+    // a version that doesn't fit in a Version (1.2.3.4.5) can't be in a Range
+    version.to_version().is_ok_and(|version| requirement.contains(version))
+  }
+}
+
+/// Composer constraints, with their stability flags and || alternatives
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Composer;
+
+impl VersionScheme for Composer {
+  type Version = Version;
+  type Requirement = Constraint;
+  type Error = ParseError;
+
+  fn parse(&self, version: &str) -> Result<Version, ParseError> {
+    Version::parse(version)
+  }
+
+  fn cmp(&self, a: &Version, b: &Version) -> Ordering {
+    cmp_with_pre(a, b)
+  }
+
+  fn parse_req(&self, requirement: &str) -> Result<Constraint, ParseError> {
+    Constraint::parse(requirement)
+  }
+
+  fn matches(&self, requirement: &Constraint, version: &Version) -> bool {
+    requirement.allows(version)
+  }
+}
+
+/// Calendar versions in this format, requirements are Ranges over their numbers (^24.4 for YY.0M)
+impl VersionScheme for CalVerFormat {
+  type Version = CalVer;
+  type Requirement = Range;
+  type Error = CalVerError;
+
+  fn parse(&self, version: &str) -> Result<CalVer, CalVerError> {
+    CalVer::parse(version, self)
+  }
+
+  fn cmp(&self, a: &CalVer, b: &CalVer) -> Ordering {
+    // both have this format, so they can always be compared
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+  }
+
+  fn parse_req(&self, requirement: &str) -> Result<Range, CalVerError> {
+    Range::parse(requirement).map_err(|_| CalVerError::InvalidRequirement(requirement.to_string()))
+  }
+
+  fn matches(&self, requirement: &Range, version: &CalVer) -> bool {
+    // a version that doesn't fit in a Version (24.04-rc.1) can't be in a Range
+    version.to_version().is_ok_and(|version| requirement.contains(version))
+  }
+}
@@ -0,0 +1,136 @@
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+use crate::implementations::Version;
+
+// The SemVer 2.0.0 rules, https://semver.org/#backusnaur-form-grammar-for-valid-semver-versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+  NotEmpty,
+  ThreeComponents,
+  NumericComponent,
+  NoLeadingZeros,
+  FitsInU32,
+  IdentifierNotEmpty,
+  IdentifierCharacters,
+  // not a SemVer rule, Version stores the pre-release as one base 36 number.
+  // Only parse breaks it, validate doesn't build a Version
+  Representable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{rule} (at byte {at})")]
+pub struct StrictError {
+  pub rule: Rule,
+  // byte offset in the input where the rule was broken
+  pub at: usize,
+}
+
+impl Display for Rule {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", match self {
+      Self::NotEmpty => "a version can't be empty",
+      Self::ThreeComponents => "a version has exactly three numbers, major.minor.patch",
+      Self::NumericComponent => "major, minor and patch can only have digits",
+      Self::NoLeadingZeros => "numbers (and numeric pre-release identifiers) can't have leading zeros",
+      Self::FitsInU32 => "major, minor and patch have to fit in 32 bits",
+      Self::IdentifierNotEmpty => "pre-release and build identifiers can't be empty",
+      Self::IdentifierCharacters => "pre-release and build identifiers can only have ASCII letters, digits and hyphens",
+      Self::Representable => "pre-releases with dots or hyphens can't be stored in a Version yet",
+    })
+  }
+}
+
+fn error<T>(rule: Rule, at: usize) -> Result<T, StrictError> {
+  Err(StrictError { rule, at })
+}
+
+// dot separated identifiers starting at byte offset, numeric ones can't have leading zeros in the pre-release
+fn identifiers(s: &str, offset: usize, pre: bool) -> Result<(), StrictError> {
+  let mut at = offset;
+  for identifier in s.split('.') {
+    if identifier.is_empty() {
+      return error(Rule::IdentifierNotEmpty, at);
+    }
+    if let Some(i) = identifier.find(|c: char| !c.is_ascii_alphanumeric() && c != '-') {
+      return error(Rule::IdentifierCharacters, at + i);
+    }
+    let numeric = identifier.chars().all(|c| c.is_ascii_digit());
+    if pre && numeric && identifier.len() > 1 && identifier.starts_with('0') {
+      return error(Rule::NoLeadingZeros, at);
+    }
+    at += identifier.len() + 1;
+  }
+  Ok(())
+}
+
+fn component(s: &str, at: usize) -> Result<u32, StrictError> {
+  if s.is_empty() {
+    return error(Rule::NumericComponent, at);
+  }
+  if let Some(i) = s.find(|c: char| !c.is_ascii_digit()) {
+    return error(Rule::NumericComponent, at + i);
+  }
+  if s.len() > 1 && s.starts_with('0') {
+    return error(Rule::NoLeadingZeros, at);
+  }
+  s.parse().or(error(Rule::FitsInU32, at))
+}
+
+// major.minor.patch, the pre-release and the build, as written
+struct Parts<'a> {
+  numbers: Vec<u32>,
+  core: &'a str,
+  pre: Option<&'a str>,
+  build: Option<&'a str>,
+}
+
+/// Checks that version is exactly what SemVer 2.0.0 allows: 1.2.3, 1.2.3-alpha.1, 1.2.3+build.5, 1.2.3-rc.1+build.5.
+/// Nothing is trimmed or lowercased and there's no v, extra version or build before pre-release
+/// (1.2.3+build-rc is a build of build-rc, like SemVer says). The error is the first broken rule from the left.
+pub fn validate(version: &str) -> Result<(), StrictError> {
+  split(version).map(|_| ())
+}
+
+/// validate, and then the Version. Valid versions a Version can't store (1.2.3-alpha.1, 1.2.3-x-y)
+/// fail with Rule::Representable.
+pub fn parse(version: &str) -> Result<Version, StrictError> {
+  let Parts { numbers, core, pre, build } = split(version)?;
+  Version::new_w_extra(numbers[0], numbers[1], numbers[2], None, pre, build)
+    .or(error(Rule::Representable, core.len() + 1))
+}
+
+fn split(version: &str) -> Result<Parts<'_>, StrictError> {
+  if version.is_empty() {
+    return error(Rule::NotEmpty, 0);
+  }
+  // everything after the first + is the build, and the pre-release is after the first - before that
+  let (rest, build) = match version.split_once('+') {
+    Some((rest, build)) => (rest, Some(build)),
+    None => (version, None),
+  };
+  let (core, pre) = match rest.split_once('-') {
+    Some((core, pre)) => (core, Some(pre)),
+    None => (rest, None),
+  };
+
+  // in the order they're written, so the error is the leftmost one
+  let mut numbers = vec![];
+  let mut at = 0;
+  for (i, part) in core.split('.').enumerate() {
+    if i == 3 {
+      return error(Rule::ThreeComponents, at - 1);
+    }
+    numbers.push(component(part, at)?);
+    at += part.len() + 1;
+  }
+  if numbers.len() < 3 {
+    return error(Rule::ThreeComponents, core.len());
+  }
+  if let Some(pre) = pre {
+    identifiers(pre, core.len() + 1, true)?;
+  }
+  if let Some(build) = build {
+    identifiers(build, rest.len() + 1, false)?;
+  }
+  Ok(Parts { numbers, core, pre, build })
+}
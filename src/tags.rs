@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+use crate::implementations::Version;
+use crate::scheme::{SemVer, VersionScheme};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Part {
+  Literal(String),
+  Component,
+  Version,
+}
+
+// v{version}, release-{version}, {component}@{version}, {component}/v{version}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagPattern {
+  parts: Vec<Part>,
+}
+
+/// Reads tag names with the first pattern that matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagParser {
+  pub patterns: Vec<TagPattern>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TagError {
+  #[error("invalid tag pattern {0}: it needs one {{version}}, at most one {{component}} and text between them")]
+  InvalidPattern(String),
+}
+
+impl TagPattern {
+  pub fn parse<S: Into<String>>(pattern: S) -> Result<Self, TagError> {
+    let pattern = pattern.into();
+    let invalid = || TagError::InvalidPattern(pattern.clone());
+    let mut parts = vec![];
+    let mut rest = pattern.as_str();
+    while !rest.is_empty() {
+      let (part, len) = if rest.starts_with("{version}") {
+        (Part::Version, "{version}".len())
+      } else if rest.starts_with("{component}") {
+        (Part::Component, "{component}".len())
+      } else {
+        // up to the next {, after the first char (which can be more than one byte, é{version})
+        let first = rest.chars().next().map_or(0, char::len_utf8);
+        let len = rest[first..].find('{').map_or(rest.len(), |i| i + first);
+        (Part::Literal(rest[..len].to_string()), len)
+      };
+      // two placeholders next to each other can't be told apart
+      if !matches!(part, Part::Literal(_)) && matches!(parts.last(), Some(Part::Component | Part::Version)) {
+        return Err(invalid());
+      }
+      parts.push(part);
+      rest = &rest[len..];
+    }
+    let count = |p: &Part| parts.iter().filter(|part| *part == p).count();
+    if count(&Part::Version) != 1 || count(&Part::Component) > 1 {
+      return Err(invalid());
+    }
+    Ok(Self { parts })
+  }
+
+  /// The component (None if the pattern has none) and version of tag, None if it doesn't match
+  pub fn matches(&self, tag: &str) -> Option<(Option<String>, Version)> {
+    match match_parts(&self.parts, tag, (None, None))? {
+      (component, Some(version)) => Some((component, version)),
+      _ => None,
+    }
+  }
+}
+
+// tries every place a placeholder could end, the last ones first so "@scope/pkg@1.2.3" is "@scope/pkg"
+fn match_parts(parts: &[Part], text: &str, found: (Option<String>, Option<Version>)) -> Option<(Option<String>, Option<Version>)> {
+  let (component, version) = found;
+  match parts {
+    [] => text.is_empty().then_some((component, version)),
+    [Part::Literal(literal), rest @ ..] => match_parts(rest, text.strip_prefix(literal.as_str())?, (component, version)),
+    [Part::Component] => (!text.is_empty()).then(|| (Some(text.to_string()), version)),
+    [Part::Version] => Some((component, Some(Version::parse(text).ok()?))),
+    [placeholder, Part::Literal(literal), rest @ ..] => {
+      text.rmatch_indices(literal.as_str()).find_map(|(i, _)| {
+        let value = &text[..i];
+        let after = &text[i + literal.len()..];
+        let found = match placeholder {
+          Part::Component if !value.is_empty() => (Some(value.to_string()), version.clone()),
+          Part::Version => (component.clone(), Some(Version::parse(value).ok()?)),
+          _ => return None,
+        };
+        match_parts(rest, after, found)
+      })
+    }
+    _ => None,
+  }
+}
+
+impl Display for TagPattern {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for part in &self.parts {
+      match part {
+        Part::Literal(literal) => write!(f, "{}", literal)?,
+        Part::Component => write!(f, "{{component}}")?,
+        Part::Version => write!(f, "{{version}}")?,
+      }
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for TagPattern {
+  type Err = TagError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+impl Default for TagParser {
+  /// v1.2.3, 1.2.3, release-1.2.3, myapp@1.2.3, myapp/v1.2.3 and myapp/1.2.3
+  fn default() -> Self {
+    let patterns = ["v{version}", "{version}", "release-{version}", "{component}@{version}", "{component}/v{version}", "{component}/{version}"];
+    Self { patterns: patterns.iter().map(|p| TagPattern::parse(*p).unwrap()).collect() }
+  }
+}
+
+impl TagParser {
+  pub fn new(patterns: Vec<TagPattern>) -> Self {
+    Self { patterns }
+  }
+
+  pub fn parse(&self, tag: &str) -> Option<(Option<String>, Version)> {
+    self.patterns.iter().find_map(|p| p.matches(tag))
+  }
+
+  /// The latest tag (name and version) of each component, tags that don't match any pattern are skipped.
+  /// Pre-releases count, 1.3.0-rc1 is newer than 1.2.0.
+  pub fn latest<S: AsRef<str>>(&self, tags: &[S]) -> BTreeMap<Option<String>, (String, Version)> {
+    let mut latest: BTreeMap<Option<String>, (String, Version)> = BTreeMap::new();
+    for tag in tags {
+      let tag = tag.as_ref();
+      let Some((component, version)) = self.parse(tag) else { continue };
+      match latest.get(&component) {
+        Some((_, current)) if !SemVer.cmp(&version, current).is_gt() => {}
+        _ => {
+          latest.insert(component, (tag.to_string(), version));
+        }
+      }
+    }
+    latest
+  }
+
+  /// latest for the tags of the git repository in repo (its work tree or the .git directory)
+  pub fn latest_in_repo<P: AsRef<Path>>(&self, repo: P) -> io::Result<BTreeMap<Option<String>, (String, Version)>> {
+    Ok(self.latest(&read_tags(repo)?))
+  }
+}
+
+// the directory with the refs: the .git directory of a work tree, or a bare repository itself
+fn git_dir(repo: &Path) -> io::Result<PathBuf> {
+  let dot_git = repo.join(".git");
+  if dot_git.is_dir() {
+    return Ok(dot_git);
+  }
+  // worktrees and submodules have a .git file with "gitdir: <path>"
+  if dot_git.is_file() {
+    let content = fs::read_to_string(&dot_git)?;
+    if let Some(dir) = content.trim().strip_prefix("gitdir:") {
+      let dir = repo.join(dir.trim());
+      // a linked worktree's gitdir (.git/worktrees/<name>) only has its HEAD, the refs are in the
+      // main repository's, which its commondir file points to (relative to the gitdir)
+      return match fs::read_to_string(dir.join("commondir")) {
+        Ok(common) => Ok(dir.join(common.trim())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(dir),
+        Err(e) => Err(e),
+      };
+    }
+  }
+  if repo.join("refs").is_dir() {
+    return Ok(repo.to_path_buf());
+  }
+  Err(io::Error::new(io::ErrorKind::NotFound, format!("{} isn't a git repository", repo.display())))
+}
+
+fn loose_tags(dir: &Path, prefix: &str, tags: &mut Vec<String>) -> io::Result<()> {
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+    if entry.file_type()?.is_dir() {
+      loose_tags(&entry.path(), &format!("{}/", name), tags)?;
+    } else {
+      tags.push(name);
+    }
+  }
+  Ok(())
+}
+
+/// The tag names of a local git repository, read from refs/tags and packed-refs, sorted.
+/// Only reads files, git isn't run and nothing is fetched.
+pub fn read_tags<P: AsRef<Path>>(repo: P) -> io::Result<Vec<String>> {
+  let git_dir = git_dir(repo.as_ref())?;
+  let mut tags = vec![];
+  let loose = git_dir.join("refs").join("tags");
+  if loose.is_dir() {
+    loose_tags(&loose, "", &mut tags)?;
+  }
+  // "<sha> refs/tags/v1.2.3", followed by "^<sha>" for annotated tags
+  match fs::read_to_string(git_dir.join("packed-refs")) {
+    Ok(packed) => {
+      tags.extend(packed.lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter_map(|(_, name)| name.strip_prefix("refs/tags/"))
+        .map(|name| name.to_string()));
+    }
+    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+    Err(e) => return Err(e),
+  }
+  tags.sort();
+  tags.dedup();
+  Ok(tags)
+}
@@ -161,4 +161,1384 @@ fn special_parse_range() {
   assert_eq!(v("1.0.0+windows.1").build, Some("windows.1".to_string()));
   assert!(v("1.0.0-62747").is_older_than(&v("1.0.0-62748")));
   assert!(v("1.0.0-62747+12345").is_older_than_with_build(&v("1.0.0-62748+12345")));
-}
\ No newline at end of file
+}
+#[test]
+fn include_only_ranges() {
+  // =1.2.3 is only 1.2.3, not every version with no min or max
+  assert!(r("=1.2.3").contains(v("1.2.3")));
+  assert!(!r("=1.2.3").contains(v("1.2.4")));
+  assert!(!r("=1.2.3").contains(v("0.1.0")));
+  assert!(r("1.2.3 1.2.4").contains(v("1.2.4")));
+  assert!(!r("1.2.3 1.2.4").contains(v("1.2.5")));
+  // with a min or max the includes are versions on top of it
+  assert!(r(">=2.0.0, 1.2.3").contains(v("1.2.3")));
+  assert!(r(">=2.0.0, 1.2.3").contains(v("2.1.0")));
+  assert!(!r(">=2.0.0, 1.2.3").contains(v("1.2.4")));
+  // != alone still lets everything else in
+  assert!(r("!=1.2.3").contains(v("1.2.4")));
+  assert!(!r("!=1.2.3").contains(v("1.2.3")));
+}
+#[test]
+fn version_display() {
+  // pre-releases and extra versions are written as they were read, not as RadixNum writes them (RC1r36)
+  assert_eq!(v("1.2.3").to_string(), "1.2.3");
+  assert_eq!(v("1.2.3-rc1").to_string(), "1.2.3-rc1");
+  assert_eq!(v("1.2.3-RC1").to_string(), "1.2.3-rc1");
+  assert_eq!(v("1.2.3.4-alpha+linux").to_string(), "1.2.3.4-alpha+linux");
+  assert!(v(&v("1.2.3-beta2").to_string()).is(&v("1.2.3-beta2")));
+  assert_eq!(r(">=1.2.3-rc1, <1.2.5").to_string(), ">=1.2.3-rc1,<1.2.5");
+}
+#[test]
+fn debian_versions() {
+  use std::cmp::Ordering;
+  use crate::debian::{DebianVersion, Relation, RelationOp, parse_relations, verrevcmp};
+  let d = |s: &str| DebianVersion::parse(s).unwrap_or_else(|_| panic!("Failed to parse debian version: {}", s));
+
+  let parsed = d("1:2.3~rc1-0ubuntu2");
+  assert_eq!(parsed.epoch, 1);
+  assert_eq!(parsed.upstream, "2.3~rc1");
+  assert_eq!(parsed.revision, Some("0ubuntu2".to_string()));
+  assert_eq!(parsed.to_string(), "1:2.3~rc1-0ubuntu2");
+  assert_eq!(d("1.2-3-4").upstream, "1.2-3");
+  assert!(DebianVersion::parse("").is_err());
+  assert!(DebianVersion::parse("a1.0").is_err());
+  assert!(DebianVersion::parse("1.0-").is_err());
+  assert!(DebianVersion::parse("x:1.0").is_err());
+  assert!(DebianVersion::parse("1.0_1").is_err());
+
+  // same results as dpkg --compare-versions
+  assert_eq!(verrevcmp("1.0~rc1", "1.0"), Ordering::Less);
+  assert_eq!(verrevcmp("1.0~~", "1.0~"), Ordering::Less);
+  assert_eq!(verrevcmp("1.0", "1.0+"), Ordering::Less);
+  assert_eq!(verrevcmp("1.0a", "1.0+"), Ordering::Less);
+  assert_eq!(verrevcmp("1.0", "1.0a"), Ordering::Less);
+  assert_eq!(verrevcmp("1.01", "1.1"), Ordering::Equal);
+  assert_eq!(verrevcmp("1.10", "1.9"), Ordering::Greater);
+  assert!(d("1:0.1") > d("9.9"));
+  assert!(d("2.3~rc1-0ubuntu2") < d("2.3-0ubuntu1"));
+  assert!(d("1.0-1") < d("1.0-1ubuntu1"));
+  assert_eq!(d("1.0"), d("1.0-0"));
+  assert_eq!(d("0:1.0"), d("1.0"));
+
+  assert_eq!(Version::try_from(&d("1.2.3~rc1")).unwrap(), v("1.2.3-rc1"));
+  assert!(v("1.2.3-rc1").is(&Version::try_from(&d("1.2.3~rc1")).unwrap()));
+  assert!(Version::try_from(&d("1.2")).is_err());
+  assert!(Version::try_from(&d("1:1.2.3")).is_err());
+  assert!(Version::try_from(&d("1.2.3-1")).is_err());
+  assert_eq!(DebianVersion::try_from(&v("1.2.3.4-rc1")).unwrap().to_string(), "1.2.3.4~rc1");
+  assert!(DebianVersion::try_from(&v("1.2.3+linux")).is_err());
+
+  let relation = Relation::parse("libc6:amd64 (>= 2.34)").unwrap();
+  assert_eq!(relation.name, "libc6");
+  assert_eq!(relation.arch, Some("amd64".to_string()));
+  assert_eq!(relation.constraint, Some((RelationOp::Ge, d("2.34"))));
+  assert!(relation.satisfied_by(&d("2.35-0ubuntu3")));
+  assert!(!relation.satisfied_by(&d("2.34~rc1")));
+  assert!(Relation::parse("foo (<< 2.0)").unwrap().satisfied_by(&d("2.0~beta")));
+  assert!(!Relation::parse("foo (>> 2.0)").unwrap().satisfied_by(&d("2.0-0")));
+  assert!(Relation::parse("foo (= 1:1.0-1)").unwrap().satisfied_by(&d("1:1.0-1")));
+  assert!(Relation::parse("foo (=> 1.0)").is_err());
+  let relations = parse_relations("a (>= 1), b | c (<< 2),d").unwrap();
+  assert_eq!(relations.len(), 3);
+  assert_eq!(relations[1].len(), 2);
+  assert_eq!(relations[1][1].to_string(), "c (<< 2)");
+}
+
+#[test]
+fn rpm_versions() {
+  use std::cmp::Ordering;
+  use crate::rpm::{Evr, RpmOp, parse_requires, rpmvercmp};
+  let e = |s: &str| Evr::parse(s).unwrap_or_else(|_| panic!("Failed to parse evr: {}", s));
+
+  let parsed = e("2:1.2.3-4.el9");
+  assert_eq!(parsed.epoch, Some(2));
+  assert_eq!(parsed.version, "1.2.3");
+  assert_eq!(parsed.release, Some("4.el9".to_string()));
+  assert_eq!(parsed.to_string(), "2:1.2.3-4.el9");
+  assert!(Evr::parse("x:1").is_err());
+  assert!(Evr::parse("1-").is_err());
+
+  // same results as rpmdev-vercmp
+  assert_eq!(rpmvercmp("1.0", "1.0"), Ordering::Equal);
+  assert_eq!(rpmvercmp("1.0", "1.0.1"), Ordering::Less);
+  assert_eq!(rpmvercmp("2.10", "2.9"), Ordering::Greater);
+  assert_eq!(rpmvercmp("1.001", "1.1"), Ordering::Equal);
+  assert_eq!(rpmvercmp("1.0a", "1.0"), Ordering::Greater);
+  assert_eq!(rpmvercmp("1a", "1.0"), Ordering::Less);
+  assert_eq!(rpmvercmp("1.0_1", "1.0.1"), Ordering::Equal);
+  assert_eq!(rpmvercmp("1.0~rc1", "1.0"), Ordering::Less);
+  assert_eq!(rpmvercmp("1.0~rc1", "1.0~rc2"), Ordering::Less);
+  assert_eq!(rpmvercmp("1.0^", "1.0"), Ordering::Greater);
+  assert_eq!(rpmvercmp("1.0^git1", "1.0.1"), Ordering::Less);
+  assert_eq!(rpmvercmp("1.0^git1", "1.0^git2"), Ordering::Less);
+  assert_eq!(rpmvercmp("1.0~rc1^git1", "1.0~rc1"), Ordering::Greater);
+  assert!(e("1:0.1") > e("9.9"));
+  assert!(e("1.2-1") < e("1.2-2"));
+  assert_eq!(e("0:1.2-1"), e("1.2-1"));
+
+  let requires = parse_requires("Requires: foo >= 1.2-3, bar baz<2 perl(Foo::Bar)").unwrap();
+  assert_eq!(requires.len(), 4);
+  assert_eq!(requires[0].constraint, Some((RpmOp::Ge, e("1.2-3"))));
+  assert_eq!(requires[2].to_string(), "baz < 2");
+  assert_eq!(requires[3].name, "perl(Foo::Bar)");
+  assert!(requires[0].satisfied_by(&e("1.2-3")));
+  assert!(!requires[0].satisfied_by(&e("1.2-2")));
+  assert!(requires[0].satisfied_by(&e("1.3")));
+  assert!(requires[1].satisfied_by(&e("0.1")));
+  assert!(parse_requires("foo = 1.2").unwrap()[0].satisfied_by(&e("1.2-7")));
+  assert!(parse_requires("BuildRequires(pre): foo >=").is_err());
+
+  let dependency = requires[0].to_dependency().unwrap();
+  assert_eq!(dependency.name, "foo");
+  assert_eq!(dependency.range.min, Some(Version::new(1, 2, 0).with_build(Some("3"))));
+  assert!(dependency.range.contains(v("1.3")));
+  assert_eq!(requires[2].to_dependency().unwrap().range, r("<2"));
+  assert!(requires[1].to_dependency().unwrap().range.is_any());
+  assert!(parse_requires("foo >= 1:1.2").unwrap()[0].to_dependency().is_err());
+}
+
+#[test]
+fn rubygems_ranges() {
+  use crate::rubygems::{GemVersion, parse_range};
+  let g = |s: &str| GemVersion::parse(s).unwrap_or_else(|_| panic!("Failed to parse gem version: {}", s));
+  let gr = |s: &str| parse_range(s).unwrap_or_else(|_| panic!("Failed to parse gem requirement: {}", s));
+
+  // Gem::Version ordering
+  assert_eq!(g("1.0"), g("1"));
+  assert_eq!(g("1.0.0"), g("1"));
+  assert_eq!(g("1.0.0rc1"), g("1.0.0.rc.1"));
+  assert!(g("1.0.a") < g("1.0"));
+  assert!(g("1.0.a") < g("1.0.b"));
+  assert!(g("1.0.rc1") < g("1.0.rc2"));
+  assert!(g("1.0.rc10") > g("1.0.rc9"));
+  assert!(g("1.0.0.1") > g("1.0"));
+  assert!(g("1.10") > g("1.9"));
+  assert!(g("2.0.0.pre") > g("1.9.9"));
+  assert!(g("1.0.a").is_prerelease());
+  assert!(!g("1.0").is_prerelease());
+  assert_eq!(g("2.1").bump(), g("3"));
+  assert_eq!(g("2.1.0").bump(), g("2.2"));
+  assert_eq!(g("2").bump(), g("3"));
+  assert_eq!(g("2.1.rc1").bump(), g("3"));
+  assert_eq!(g("1.2.3.4.rc.1").to_version().unwrap(), Version::new_w_extra(1, 2, 3, Some("4"), Some("rc1"), None).unwrap());
+  assert!(g("1.2.3.4.10").to_version().is_err());
+  assert!(g("1.0.rc.1.2").to_version().is_err());
+  assert!(g("4294967296.0").to_version().is_err());
+  assert!(GemVersion::parse("1..2").is_err());
+
+  assert_eq!(gr("~> 2.1"), r(">=2.1 <3.0"));
+  assert_eq!(gr("~> 2.1.0"), r(">=2.1.0 <2.2"));
+  assert_eq!(gr("~>2"), r(">=2 <3"));
+  assert_eq!(gr("~> 2.1, != 2.1.5"), r(">=2.1 <3.0 !=2.1.5"));
+  assert_eq!(gr(">= 1.0, < 2"), r(">=1.0 <2"));
+  assert_eq!(gr("~> 5.2, >= 5.2.4"), r(">=5.2.4 <6"));
+  assert_eq!(gr("~> 2.1, < 2.5"), r(">=2.1 <2.5"));
+  assert!(!gr("~> 2.1, >= 2.1.3").contains(v("2.1.0")));
+  assert!(!gr("~> 2.1, < 2.5").contains(v("2.9.0")));
+  assert_eq!(gr("= 1.0"), r("=1.0"));
+  assert_eq!(gr("1.0"), r("=1.0"));
+  assert!(gr("~> 2.1").contains(v("2.9.9")));
+  assert!(!gr("~> 2.1.0").contains(v("2.2.0")));
+  assert!(parse_range("~> ").is_err());
+  assert!(parse_range("=> 1.0").is_err());
+  assert!(parse_range("~> 1.2.3.4.10").is_err());
+  assert!(parse_range(">= 4294967296").is_err());
+}
+
+#[test]
+fn composer_constraints() {
+  use crate::composer::{Constraint, Stability, parse_range};
+  use crate::RangeSet;
+  let c = |s: &str| Constraint::parse(s).unwrap_or_else(|_| panic!("Failed to parse composer constraint: {}", s));
+  let cr = |s: &str| parse_range(s).unwrap_or_else(|_| panic!("Failed to parse composer constraint: {}", s));
+
+  assert_eq!(cr("^1.2 || ^2.0"), RangeSet::new(vec![r("^1.2"), r("^2.0")]));
+  assert_eq!(cr("~1.2"), RangeSet::from(r(">=1.2 <2.0")));
+  assert_eq!(cr("~1.2.3"), RangeSet::from(r(">=1.2.3 <1.3.0")));
+  assert_eq!(cr("~1"), RangeSet::from(r(">=1 <2")));
+  assert_eq!(cr("^0.3"), RangeSet::from(r(">=0.3 <0.4")));
+  assert_eq!(cr("^0.0.3"), RangeSet::from(r(">=0.0.3 <0.0.4")));
+  assert_eq!(cr("1.0.*"), RangeSet::from(r(">=1.0 <1.1")));
+  assert_eq!(cr("v1.*"), RangeSet::from(r(">=1 <2")));
+  assert_eq!(cr(">=1.0 <2.0"), RangeSet::from(r(">=1.0 <2.0")));
+  assert_eq!(cr(">=1.0, <1.5 || >=2"), RangeSet::new(vec![r(">=1.0 <1.5"), r(">=2")]));
+  assert_eq!(cr("^1.2 <1.5"), RangeSet::from(r(">=1.2 <1.5")));
+  assert_eq!(cr("1.0 - 2.0"), RangeSet::from(r(">=1.0 <2.1")));
+  assert_eq!(cr("1.0.0 - 2.0.0"), RangeSet::from(r(">=1.0.0 <=2.0.0")));
+  assert!(cr("*").is_any());
+  assert!(cr("1.0.3").contains(v("1.0.3")));
+  assert!(!cr("1.0.3").contains(v("1.0.4")));
+  assert!(cr("^1.2 || ^3.0").contains(v("3.1")));
+  assert!(!cr("^1.2 || ^3.0").contains(v("2.1")));
+  assert!(Constraint::parse("=> 1.0").is_err());
+  assert!(Constraint::parse("^1.0@foo").is_err());
+  assert!(Constraint::parse("1.0.0-alpha20240101").is_err());
+  assert!(Constraint::parse("^1.0-alpha20240101").is_err());
+  assert!(Constraint::parse("1.0-alpha20240101 - 2.0").is_err());
+  assert!(Constraint::parse("~4294967295").is_err());
+  assert!(Constraint::parse("1.4294967295.*").is_err());
+
+  // stability
+  assert_eq!(Stability::of(&v("1.0.0")), Stability::Stable);
+  assert_eq!(Stability::of(&v("1.0.0-beta2")), Stability::Beta);
+  assert_eq!(Stability::of(&v("1.0.0-b2")), Stability::Beta);
+  assert_eq!(Stability::of(&v("1.0.0-RC1")), Stability::RC);
+  assert_eq!(Stability::of(&v("1.0.0-alpha")), Stability::Alpha);
+  assert_eq!(Stability::of(&v("1.0.0-dev")), Stability::Dev);
+  assert_eq!(Stability::of(&v("1.0.0-patch1")), Stability::Stable);
+  assert!(c("^1.0").allows(&v("1.2.0")));
+  assert!(!c("^1.0").allows(&v("1.2.0-beta1")));
+  assert!(c("^1.0@beta").allows(&v("1.2.0-beta1")));
+  assert!(!c("^1.0@beta").allows(&v("1.2.0-alpha1")));
+  assert!(c(">=1.0.0-alpha1").allows(&v("1.2.0-alpha2")));
+  assert_eq!(c("^1.0@dev || ^2.0").min_stability, Stability::Dev);
+  assert!(c("@dev").allows(&v("5.0.0-dev")));
+
+  // branches
+  let branch = c("dev-main as 1.0.x-dev || ^1.0");
+  assert!(branch.allows_branch("dev-main"));
+  assert!(branch.allows_branch("main"));
+  assert!(!branch.allows_branch("dev-develop"));
+  assert_eq!(branch.branches[0].alias, Some("1.0.x-dev".to_string()));
+  assert_eq!(c("1.0.x-dev").min_stability, Stability::Dev);
+  assert!(c("1.0.x-dev").allows(&v("1.0.5-dev")));
+}
+
+#[test]
+fn gomod_versions() {
+  use std::collections::HashMap;
+  use crate::gomod::{ModRequirement, ModRequirements, ModVersion, minimal_version_selection};
+  let g = |s: &str| ModVersion::parse(s).unwrap_or_else(|_| panic!("Failed to parse go version: {}", s));
+
+  assert_eq!(g("v1.2.3").to_string(), "v1.2.3");
+  assert_eq!(g("v2.0.0+incompatible").build, Some("incompatible".to_string()));
+  assert!(g("v2.0.0+incompatible").is_incompatible());
+  assert!(ModVersion::parse("1.2.3").is_err());
+  assert!(ModVersion::parse("v1.2").is_err());
+  assert!(ModVersion::parse("v01.2.3").is_err());
+  assert!(ModVersion::parse("v1.2.3-01").is_err());
+
+  // semver ordering, pre-releases included
+  assert!(g("v1.0.0-alpha") < g("v1.0.0"));
+  assert!(g("v1.0.0-alpha") < g("v1.0.0-alpha.1"));
+  assert!(g("v1.0.0-alpha.1") < g("v1.0.0-alpha.beta"));
+  assert!(g("v1.0.0-beta.2") < g("v1.0.0-beta.11"));
+  assert!(g("v1.0.0-rc.1") < g("v1.0.0"));
+  assert_eq!(g("v2.0.0+incompatible"), g("v2.0.0"));
+
+  // pseudo-versions
+  let pseudo = g("v0.0.0-20191109021931-daa7c04131f5").pseudo().unwrap();
+  assert_eq!(pseudo.base, None);
+  assert_eq!(pseudo.timestamp.to_string(), "20191109021931");
+  assert_eq!((pseudo.timestamp.year, pseudo.timestamp.month, pseudo.timestamp.second), (2019, 11, 31));
+  assert_eq!(pseudo.revision, "daa7c04131f5");
+  assert_eq!(g("v1.2.4-0.20191109021931-daa7c04131f5").pseudo().unwrap().base, Some(g("v1.2.3")));
+  assert_eq!(g("v1.2.3-pre.0.20191109021931-daa7c04131f5").pseudo().unwrap().base, Some(g("v1.2.3-pre")));
+  assert!(!g("v1.2.3").is_pseudo());
+  assert!(!g("v1.2.3-pre").is_pseudo());
+  assert!(!g("v1.2.0-20191109021931-daa7c04131f5").is_pseudo());
+  assert!(!g("v0.0.0-20191339021931-daa7c04131f5").is_pseudo());
+  // a pseudo-version is newer than its base and older than the next release
+  assert!(g("v1.2.4-0.20191109021931-daa7c04131f5") > g("v1.2.3"));
+  assert!(g("v1.2.4-0.20191109021931-daa7c04131f5") < g("v1.2.4"));
+  assert!(g("v1.2.4-0.20191109021931-daa7c04131f5") < g("v1.2.4-0.20200101000000-aaaaaaaaaaaa"));
+
+  // major version suffix
+  assert!(g("v1.5.0").check_path("example.com/m").is_ok());
+  assert!(g("v0.1.0").check_path("example.com/m").is_ok());
+  assert!(g("v2.0.0").check_path("example.com/m").is_err());
+  assert!(g("v2.0.0+incompatible").check_path("example.com/m").is_ok());
+  assert!(g("v1.0.0+incompatible").check_path("example.com/m").is_err());
+  assert!(g("v2.1.0").check_path("example.com/m/v2").is_ok());
+  assert!(g("v3.0.0").check_path("example.com/m/v2").is_err());
+  assert!(g("v2.0.0+incompatible").check_path("example.com/m/v2").is_err());
+  assert!(g("v1.0.0").check_path("example.com/m/v1").is_ok());
+  assert!(g("v3.0.0").check_path("gopkg.in/yaml.v3").is_ok());
+  assert!(g("v2.0.0").check_path("gopkg.in/yaml.v3").is_err());
+
+  // minimal version selection, the example from https://research.swtch.com/vgo-mvs
+  struct Graph(HashMap<String, Vec<ModRequirement>>);
+  impl ModRequirements for Graph {
+    fn requirements(&self, path: &str, version: &ModVersion) -> Vec<ModRequirement> {
+      self.0.get(&format!("{}@{}", path, version)).cloned().unwrap_or_default()
+    }
+  }
+  let req = |s: &str| {
+    let (path, version) = s.split_once('@').unwrap();
+    ModRequirement::new(path, g(version))
+  };
+  let graph = Graph(HashMap::from([
+    ("b@v1.2.0".to_string(), vec![req("d@v1.3.0")]),
+    ("c@v1.2.0".to_string(), vec![req("d@v1.4.0")]),
+    ("d@v1.3.0".to_string(), vec![req("e@v1.2.0")]),
+    ("d@v1.4.0".to_string(), vec![req("e@v1.2.0"), req("c@v1.2.0")]),
+    ("e@v1.2.0".to_string(), vec![]),
+  ]));
+  let selected = minimal_version_selection(&[req("b@v1.2.0"), req("c@v1.2.0")], &graph);
+  assert_eq!(selected.len(), 4);
+  assert_eq!(selected["d"], g("v1.4.0"));
+  assert_eq!(selected["e"], g("v1.2.0"));
+  assert_eq!(selected["b"], g("v1.2.0"));
+}
+
+#[test]
+fn pep508_requirements() {
+  use std::collections::HashMap;
+  use crate::pep508::{parse_version, Marker, Requirement};
+  let p = |s: &str| Requirement::parse(s).unwrap_or_else(|_| panic!("Failed to parse requirement: {}", s));
+
+  let requirement = p(r#"requests[security,socks] >= 2.8.1, == 2.8.* ; python_version < "3.8""#);
+  assert_eq!(requirement.dependency.name, "requests");
+  assert_eq!(requirement.extras, vec!["security", "socks"]);
+  assert_eq!(requirement.dependency.range, r(">=2.8.1 <2.9"));
+  assert_eq!(requirement.marker.as_ref().unwrap().to_string(), r#"python_version < "3.8""#);
+
+  assert!(p("requests").dependency.range.is_any());
+  assert_eq!(p("requests (>=2.0,<3)").dependency.range, r(">=2.0 <3"));
+  assert_eq!(p("foo ~= 2.2").dependency.range, r(">=2.2 <3"));
+  assert_eq!(p("foo ~= 1.4.5").dependency.range, r(">=1.4.5 <1.5"));
+  assert!(p("foo == 1.2.3.4.*").dependency.range.contains(v("1.2.3.4")));
+  assert_eq!(p("foo != 1.5, >1").dependency.range, r(">1 !=1.5"));
+  assert_eq!(p("foo >= 1.0a1").dependency.range.min, Some(v("1.0-a1")));
+  assert_eq!(p("foo == 1.0.post2").dependency.range.include, vec![v("1.0+post2")]);
+  // the post-release is the build, which ranges only look at for == and !=
+  assert!(!p("foo == 1.0.post2").dependency.range.contains(v("1.0")));
+  assert!(p("foo >= 1.0.post2").dependency.range.contains(v("1.0")));
+  assert_eq!(parse_version("0!2.0").unwrap(), v("2.0"));
+  assert!(parse_version("1!2.0").is_err());
+  assert!(Requirement::parse("foo >= 1!1.0").is_err());
+  assert!(parse_version("1.2.3.4.5").is_err());
+  assert_eq!(p("zope.interface>=5").dependency.name, "zope.interface");
+  assert_eq!(p("pip @ https://github.com/pypa/pip/archive/1.3.1.zip ; python_version >= '3'").url,
+    Some("https://github.com/pypa/pip/archive/1.3.1.zip".to_string()));
+  assert!(Requirement::parse("foo != 1.5.*").is_err());
+  assert!(Requirement::parse("foo ~= 1").is_err());
+  assert!(Requirement::parse("foo => 1").is_err());
+  assert!(Requirement::parse("[security] >= 1").is_err());
+
+  let env = HashMap::from([
+    ("python_version".to_string(), "3.7".to_string()),
+    ("python_full_version".to_string(), "3.7.4".to_string()),
+    ("sys_platform".to_string(), "linux".to_string()),
+    ("os_name".to_string(), "posix".to_string()),
+  ]);
+  assert!(requirement.applies(&env).unwrap());
+  assert!(p("foo").applies(&env).unwrap());
+  let m = |s: &str| Marker::parse(s).unwrap_or_else(|_| panic!("Failed to parse marker: {}", s)).evaluate(&env).unwrap();
+  assert!(m("python_version >= '3.7'"));
+  assert!(!m("python_version > '3.7'"));
+  assert!(m("python_full_version < '3.10'"));
+  assert!(m("python_version ~= '3.1'"));
+  assert!(m("sys_platform == 'linux' and os_name != 'nt'"));
+  assert!(m("sys_platform == 'win32' or os_name == 'posix'"));
+  assert!(!m("sys_platform == 'win32' or (os_name == 'posix' and python_version >= '3.8')"));
+  assert!(m("os_name in 'posix nt'"));
+  assert!(m("'linux' not in sys_platform and os_name == 'posix' or python_version == '3.7'"));
+  assert!(Marker::parse("extra == 'socks'").unwrap().evaluate(&env).is_err());
+  let marker = Marker::parse("(os_name == 'a' or os_name == 'b') and extra == 'c'").unwrap();
+  assert_eq!(marker.to_string(), r#"(os_name == "a" or os_name == "b") and extra == "c""#);
+}
+
+#[test]
+fn calver_versions() {
+  use crate::calver::{CalVer, CalVerError, CalVerFormat, Clock, Date, Token};
+  use crate::scheme::VersionScheme;
+  struct FixedClock(Date);
+  impl Clock for FixedClock {
+    fn today(&self) -> Date {
+      self.0
+    }
+  }
+  let f = |s: &str| CalVerFormat::parse(s).unwrap_or_else(|_| panic!("Failed to parse calver format: {}", s));
+  let c = |s: &str, format: &str| CalVer::parse(s, &f(format)).unwrap_or_else(|_| panic!("Failed to parse calver: {}", s));
+
+  assert_eq!(f("YYYY.MM.MICRO").tokens, vec![Token::FullYear, Token::Month, Token::Micro]);
+  assert!(CalVerFormat::parse("YYYY.MONTH").is_err());
+  assert!(CalVerFormat::parse("MAJOR.MINOR").is_err());
+
+  assert_eq!(c("2024.10.1", "YYYY.MM.MICRO").values, vec![2024, 10, 1]);
+  assert_eq!(c("24.04", "YY.0M").to_string(), "24.04");
+  assert_eq!(c("2024.10.17-1", "YYYY.MM.DD").modifier, Some("1".to_string()));
+  assert_eq!(c("2024.10.17-1", "YYYY.MM.DD").to_string(), "2024.10.17-1");
+  assert!(CalVer::parse("24.4", &f("YY.0M")).is_err());
+  assert!(CalVer::parse("24.04", &f("YY.MM")).is_err());
+  assert!(CalVer::parse("2024.13.1", &f("YYYY.MM.MICRO")).is_err());
+  assert!(CalVer::parse("2023.2.29", &f("YYYY.MM.DD")).is_err());
+  assert!(CalVer::parse("2024.2.29", &f("YYYY.MM.DD")).is_ok());
+  assert!(CalVer::parse("2024.10", &f("YYYY.MM.MICRO")).is_err());
+  assert!(CalVer::parse("24.10.1", &f("YYYY.MM.MICRO")).is_err());
+  assert!(c("2024.9.3", "YYYY.MM.MICRO") < c("2024.10.1", "YYYY.MM.MICRO"));
+  assert!(c("2024.10.1-rc1", "YYYY.MM.MICRO") < c("2024.10.1", "YYYY.MM.MICRO"));
+  assert_eq!(c("2024.10.1", "YYYY.MM.MICRO").partial_cmp(&c("24.10", "YY.MM")), None);
+
+  // to and from Version
+  assert_eq!(c("2024.10.1", "YYYY.MM.MICRO").to_version(), Ok(Version::new(2024, 10, 1)));
+  assert_eq!(c("2024.10.17.3-rc1", "YYYY.MM.DD.MICRO").to_version(), Ok(Version::new_w_extra(2024, 10, 17, Some("3"), Some("rc1"), None).unwrap()));
+  assert!(r(">=2024.4 <2025").contains(c("24.04", "0Y.0M").to_version().unwrap().with_major(2024)));
+  assert!(r("^24.4").contains(c("24.10", "YY.0M").to_version().unwrap()));
+  // 2024.10.17.12.3 and 2024.10.17.1.23 would both be 2024.10.17.123
+  assert!(c("2024.10.17.12.3", "YYYY.MM.DD.MAJOR.MICRO").to_version().is_err());
+  assert!(c("2024.10.17.999999.9999999", "YYYY.MM.DD.MAJOR.MICRO").to_version().is_err());
+  // the modifier can't be dropped, 24.04-rc.1 isn't 24.04
+  assert_eq!(c("24.04-rc.1", "YY.0M").to_version(), Err(CalVerError::Unrepresentable("24.04-rc.1".to_string())));
+  assert!(!f("YY.0M").matches(&r(">=24.4"), &c("24.04-rc.1", "YY.0M")));
+  assert_eq!(CalVer::from_version(&v("2024.4.0"), &f("YYYY.0M.MICRO")).unwrap().to_string(), "2024.04.0");
+  assert_eq!(CalVer::from_version(&v("24.4"), &f("YY.0M")).unwrap().to_string(), "24.04");
+  assert!(CalVer::from_version(&v("24.4.1"), &f("YY.0M")).is_err());
+  assert!(CalVer::from_version(&v("2024.13.0"), &f("YYYY.MM.MICRO")).is_err());
+
+  // bump
+  let clock = FixedClock(Date::new(2024, 10, 17));
+  assert_eq!(c("2024.9.3", "YYYY.MM.MICRO").bump_with(&clock).unwrap().to_string(), "2024.10.0");
+  assert_eq!(c("2024.10.3", "YYYY.MM.MICRO").bump_with(&clock).unwrap().to_string(), "2024.10.4");
+  assert_eq!(c("2024.10.3-rc1", "YYYY.MM.MICRO").bump_with(&clock).unwrap().to_string(), "2024.10.4");
+  assert_eq!(c("24.04", "YY.0M").bump_with(&clock).unwrap().to_string(), "24.10");
+  assert_eq!(c("2024.42.1", "YYYY.WW.MICRO").bump_with(&clock).unwrap().to_string(), "2024.42.2");
+  assert!(c("24.10", "YY.0M").bump_with(&clock).is_err());
+  assert!(c("2024.11.0", "YYYY.MM.MICRO").bump_with(&clock).is_err());
+  assert!(c("2024.10.0", "YYYY.MM.MICRO").bump().is_ok());
+}
+
+#[test]
+fn version_schemes() {
+  use crate::scheme::{Composer, Debian, Pep440, Rpm, RubyGems, SemVer, VersionScheme};
+  // the same generic code for every scheme
+  fn latest<S: VersionScheme>(scheme: &S, requirement: &str, versions: &[&str]) -> Option<String> {
+    let requirement = scheme.parse_req(requirement).ok()?;
+    let versions: Vec<S::Version> = versions.iter().map(|v| scheme.parse(v)).collect::<Result<_, _>>().ok()?;
+    scheme.max_satisfying(&requirement, &versions).map(|v| v.to_string())
+  }
+  fn sorted<S: VersionScheme>(scheme: &S, versions: &[&str]) -> Vec<String> {
+    let mut versions: Vec<S::Version> = versions.iter().map(|v| scheme.parse(v).ok().unwrap()).collect();
+    scheme.sort(&mut versions);
+    versions.iter().map(|v| v.to_string()).collect()
+  }
+
+  assert_eq!(latest(&SemVer, "^1.2", &["1.2.0", "1.9.1", "2.0.0"]), Some("1.9.1".to_string()));
+  assert_eq!(latest(&SemVer, ">=3", &["1.2.0", "2.0.0"]), None);
+  assert_eq!(sorted(&SemVer, &["1.0.0", "2.0", "0.9.0"]), vec!["0.9.0", "1.0.0", "2.0.0"]);
+  assert_eq!(SemVer.cmp(&v("1.0.0-alpha"), &v("1.0.0")), std::cmp::Ordering::Less);
+  assert_eq!(SemVer.cmp(&v("1.0.0-alpha"), &v("1.0.0-beta")), std::cmp::Ordering::Less);
+  assert_eq!(SemVer.cmp(&v("1.0.0-rc9"), &v("1.0.0-rc10")), std::cmp::Ordering::Less);
+  assert_eq!(SemVer.cmp(&v("1.0.0-alpha"), &v("1.0.0-alpha1")), std::cmp::Ordering::Less);
+  assert_eq!(SemVer.cmp(&v("1.0.0-1"), &v("1.0.0-alpha")), std::cmp::Ordering::Less);
+  assert_eq!(SemVer.cmp(&v("1.0.0-beta2"), &v("1.0.0-beta11")), std::cmp::Ordering::Less);
+  assert_eq!(sorted(&SemVer, &["1.0.0-rc10", "1.0.0-rc9", "1.0.0-rc1b"]), vec!["1.0.0-rc1b", "1.0.0-rc9", "1.0.0-rc10"]);
+  assert!(SemVer.parse("not a version").is_err());
+
+  assert_eq!(latest(&Pep440, ">= 2.8.1, == 2.8.*", &["2.8.0", "2.8.3", "2.9.0"]), Some("2.8.3".to_string()));
+  assert_eq!(Pep440.cmp(&Pep440.parse("1.0a1").unwrap(), &Pep440.parse("1.0rc1").unwrap()), std::cmp::Ordering::Less);
+  assert_eq!(Pep440.cmp(&Pep440.parse("1.0rc1").unwrap(), &Pep440.parse("1.0").unwrap()), std::cmp::Ordering::Less);
+
+  assert_eq!(latest(&Debian, "libc6 (<< 2.36)", &["2.35-1", "2.36~rc1", "2.36"]), Some("2.36~rc1".to_string()));
+  assert_eq!(sorted(&Debian, &["1.0", "1.0~rc1", "1:0.9"]), vec!["1.0~rc1", "1.0", "1:0.9"]);
+
+  assert_eq!(latest(&Rpm, "foo >= 1.2-3", &["1.2-2", "1.2-4", "1.1-9"]), Some("1.2-4".to_string()));
+  assert_eq!(latest(&RubyGems, "~> 2.1", &["2.0", "2.1.5", "3.0"]), Some("2.1.5".to_string()));
+  assert_eq!(sorted(&RubyGems, &["1.0", "1.0.rc1", "1.0.a"]), vec!["1.0.a", "1.0.rc.1", "1.0"]);
+  let versions = [v("1.0.0"), v("1.1.0-beta1"), v("2.0.0")];
+  assert_eq!(Composer.max_satisfying(&Composer.parse_req("^1.0@beta").unwrap(), &versions), Some(&versions[1]));
+  assert_eq!(Composer.max_satisfying(&Composer.parse_req("^1.0").unwrap(), &versions), Some(&versions[0]));
+
+  let format = crate::calver::CalVerFormat::parse("YY.0M").unwrap();
+  assert_eq!(latest(&format, "^24.4", &["24.04", "24.10", "25.01"]), Some("24.10".to_string()));
+  assert_eq!(sorted(&format, &["24.10", "23.01", "24.04"]), vec!["23.01", "24.04", "24.10"]);
+  assert!(format.parse("24.4").is_err());
+}
+
+#[test]
+fn strict_versions() {
+  use crate::strict::{validate, Rule, StrictError};
+  let s = |version: &str| Version::parse_strict(version).unwrap_or_else(|e| panic!("Failed to parse strict version {}: {}", version, e));
+  let e = |version: &str, rule: Rule, at: usize| assert_eq!(Version::parse_strict(version), Err(StrictError { rule, at }), "{}", version);
+
+  assert_eq!(s("1.2.3"), Version::new(1, 2, 3));
+  assert_eq!(s("0.0.0"), Version::new(0, 0, 0));
+  assert_eq!(s("1.2.3-alpha"), v("1.2.3-alpha"));
+  assert_eq!(s("1.2.3-rc1+build.5"), v("1.2.3-rc1").with_build(Some("build.5")));
+  assert_eq!(s("1.2.3+build-rc"), Version::new(1, 2, 3).with_build(Some("build-rc")));
+  assert_eq!(s("1.2.3+Build.007").build, Some("Build.007".to_string()));
+
+  e("", Rule::NotEmpty, 0);
+  e("1", Rule::ThreeComponents, 1);
+  e("1.2", Rule::ThreeComponents, 3);
+  e("1.2.3.4", Rule::ThreeComponents, 5);
+  e("v1.2.3", Rule::NumericComponent, 0);
+  e("1..3", Rule::NumericComponent, 2);
+  e("1.2.", Rule::NumericComponent, 4);
+  e(" 1.2.3", Rule::NumericComponent, 0);
+  e("01.2.3", Rule::NoLeadingZeros, 0);
+  e("1.2.03", Rule::NoLeadingZeros, 4);
+  e("1.2.3-01", Rule::NoLeadingZeros, 6);
+  e("1.2.3-", Rule::IdentifierNotEmpty, 6);
+  e("1.2.3+build..1", Rule::IdentifierNotEmpty, 12);
+  e("1.2.3-alpha_1", Rule::IdentifierCharacters, 11);
+  e("1.2.3+build 1", Rule::IdentifierCharacters, 11);
+  e("99999999999.0.0", Rule::FitsInU32, 0);
+  // the leftmost broken rule, even when the build is also wrong
+  e("01.2.3+build..1", Rule::NoLeadingZeros, 0);
+  e("1.2.3-01+build..1", Rule::NoLeadingZeros, 6);
+
+  // valid SemVer that doesn't fit in a Version is only a parse error
+  e("1.2.3-alpha.1", Rule::Representable, 6);
+  e("1.0.0-x-y", Rule::Representable, 6);
+  assert_eq!(validate("1.2.3-alpha.1"), Ok(()));
+  assert_eq!(validate("1.0.0-x-y+build.1-a"), Ok(()));
+  assert_eq!(validate("1.0.0-rc.1.alpha20240101x"), Ok(()));
+  assert_eq!(validate("1.2"), Err(StrictError { rule: Rule::ThreeComponents, at: 3 }));
+  assert!(Version::parse_strict("1.2.3-01a").is_ok());
+  assert!(Version::parse_strict("1.2.3-0").is_ok());
+}
+
+#[test]
+fn lossless_versions() {
+  use crate::lossless::{Component, LosslessVersion};
+  let l = |version: &str| Version::parse_lossless(version).unwrap_or_else(|_| panic!("Failed to parse lossless version: {}", version));
+
+  for version in ["1.0-RC.01", "v1.2", "V 1.2.3", "1.", "1.2.3.4", "1.0+Win-rc1", "01.002.3", " 1.2.3-beta+Build.5 "] {
+    assert_eq!(l(version).to_string(), version);
+  }
+  let rc = l("1.0-RC.01");
+  assert_eq!(rc.version, v("1.0.0-rc01"));
+  assert_eq!(rc.text(Component::Pre), Some("RC.01"));
+  assert_eq!(rc.span(Component::Pre), Some(4..9));
+  assert!(rc.is_specified(Component::Minor));
+  assert!(!rc.is_specified(Component::Patch));
+  assert_eq!(l("1.0+Win-rc1").version, v("1.0.0-rc1+win"));
+  assert_eq!(Version::from(l("01.002.3")), Version::new(1, 2, 3));
+  assert!(Version::parse_lossless("1.2.x").is_err());
+  assert_eq!("v2".parse::<LosslessVersion>().unwrap().version, Version::new(2, 0, 0));
+
+  // rewriting only touches what changed
+  assert_eq!(l("v1.2").with_version(&Version::new(1, 3, 0)).unwrap().to_string(), "v1.3");
+  assert_eq!(l("v1.2").with_version(&Version::new(1, 2, 5)).unwrap().to_string(), "v1.2.5");
+  assert_eq!(l("1").with_version(&Version::new(1, 0, 7)).unwrap().to_string(), "1.0.7");
+  assert_eq!(l("1").with_version(&Version::new(2, 0, 0)).unwrap().to_string(), "2");
+  assert_eq!(l("1.0-RC.01").with_version(&v("2.0.0-rc01")).unwrap().to_string(), "2.0-RC.01");
+  assert_eq!(l("1.0-RC.01").with_version(&v("1.0.0")).unwrap().to_string(), "1.0");
+  assert_eq!(l("1.0-RC.01").with_version(&v("1.0.0-rc2")).unwrap().to_string(), "1.0-rc2");
+  assert_eq!(l("01.002.3").with_version(&Version::new(1, 2, 4)).unwrap().to_string(), "01.002.4");
+  assert_eq!(l(" 1.2.3-beta+Build.5 ").with_version(&v("1.2.3+build.5")).unwrap().to_string(), " 1.2.3+Build.5 ");
+  assert_eq!(l("1.2.3").with_version(&v("1.2.3-beta+linux")).unwrap().to_string(), "1.2.3-beta+linux");
+  assert_eq!(l("1.0+Win-rc1").with_version(&v("1.0.0-rc2+win")).unwrap().to_string(), "1.0+Win-rc2");
+  assert_eq!(l("1.2").with_version(&v("1.2.0.4")).unwrap().to_string(), "1.2.0.4");
+  // builds the text can't hold fail instead of being read back as something else
+  assert!(l("1.2.3").with_version(&Version::new(1, 2, 3).with_build(Some("a/b"))).is_err());
+  assert!(l("1.2.3").with_version(&Version::new(1, 2, 3).with_build(Some("linux-x64"))).is_err());
+  assert!(Version::parse_lossless("1.0-nightly.20240101").is_err());
+  // the dots and underscores of the pre-release are only in the text, these are all 1.0.0-rc1
+  for version in ["1.0-rc.1", "1.0-rc_1", "1.0-RC1", "1.0-r.c.1", "1.0-rc1."] {
+    assert!(l(version).version.is(&v("1.0.0-rc1")), "{}", version);
+  }
+  assert_ne!(l("1.0-rc.1"), l("1.0-rc1"));
+  // but not in the extra version, 1.2.3.4.5 would be 1.2.3.45
+  assert!(Version::parse_lossless("1.2.3.4.5").is_err());
+  assert!(Version::parse_lossless("1.2.3.4_5").is_err());
+  assert_eq!(l("1.2.3.45").version, Version::new_w_extra(1, 2, 3, Some("45"), None, None).unwrap());
+}
+
+#[test]
+fn parse_reports() {
+  use crate::diagnostics::{Warning, WarningKind};
+  use crate::lossless::Component;
+  let w = |kind: WarningKind, span: std::ops::Range<usize>| Warning { kind, span };
+  let report = |version: &str| Version::parse_with_report(version).unwrap_or_else(|_| panic!("Failed to parse version: {}", version));
+
+  assert_eq!(report("1.2.3"), (Version::new(1, 2, 3), vec![]));
+  assert_eq!(report("V 1."), (Version::new(1, 0, 0), vec![
+    w(WarningKind::LeadingV, 0..1),
+    w(WarningKind::Whitespace, 1..2),
+    w(WarningKind::MissingComponent(Component::Minor), 3..3),
+    w(WarningKind::MissingComponent(Component::Patch), 3..3),
+    w(WarningKind::TrailingSeparator, 3..4),
+  ]));
+  assert_eq!(report("1.0+win-rc"), (v("1.0.0-rc+win"), vec![
+    w(WarningKind::MissingComponent(Component::Patch), 3..3),
+    w(WarningKind::BuildBeforePre, 3..10),
+  ]));
+  assert_eq!(report("1.2.03-RC1+Linux").1, vec![
+    w(WarningKind::LeadingZeros(Component::Patch), 4..6),
+    w(WarningKind::UppercaseFolded(Component::Pre), 7..10),
+    w(WarningKind::UppercaseFolded(Component::Build), 11..16),
+  ]);
+  // the lossless text keeps what parse rejects, its report says what was dropped
+  assert_eq!(crate::diagnostics::report(&Version::parse_lossless("1.0.0-rc.1").unwrap()), vec![
+    w(WarningKind::SeparatorsDropped(Component::Pre), 6..10),
+  ]);
+  assert_eq!(report(" 1.2.3, ").1, vec![
+    w(WarningKind::Whitespace, 0..1),
+    w(WarningKind::TrailingSeparator, 6..7),
+    w(WarningKind::Whitespace, 7..8),
+  ]);
+  assert_eq!(report("v1.2.3").1[0].to_string(), "the leading v was ignored (at 0..1)");
+  assert!(Version::parse_with_report("1.2.x").is_err());
+  // it fails where parse does, not where the lossless parser does
+  for version in ["1.2.3.4.5", "1.0-rc.1", "1.0.0-a_b", "1.0.0-abcdefghijklm"] {
+    assert!(Version::parse(version).is_err(), "{}", version);
+    assert!(Version::parse_with_report(version).is_err(), "{}", version);
+  }
+}
+
+#[test]
+fn parse_error_rendering() {
+  use crate::diagnostics::{render, Diagnostic};
+  let d = |range: &str| Diagnostic::from_error(range, &Range::parse(range).unwrap_err());
+
+  let arrow = d("=>1.2.3");
+  assert_eq!(arrow.span, 1..2);
+  assert_eq!(arrow.suggestions.len(), 1);
+  assert_eq!(arrow.fixed(), Some(">=1.2.3".to_string()));
+  assert!(r(&arrow.fixed().unwrap()).contains(v("1.2.3")));
+  assert_eq!(render("=>1.2.3", &Range::parse("=>1.2.3").unwrap_err()), [
+    "=>1.2.3",
+    " ^ unexpected `>`, expected a space, a number or v",
+    "help: did you mean `>=`? `=>` isn't an operator",
+  ].join("\n"));
+
+  let unicode = d("≥1.2 ≤2");
+  assert_eq!(unicode.fixed(), Some(">=1.2 <=2".to_string()));
+  assert!(unicode.to_string().starts_with("≥1.2 ≤2\n^ unexpected `≥`"));
+  assert_eq!(d(">=1 && <2").fixed(), Some(">=1  <2".to_string()));
+
+  let alternatives = d(">=1.2 || 2");
+  assert_eq!(alternatives.fixed(), None);
+  assert!(alternatives.to_string().contains("help: a Range can't have alternatives"));
+  assert!(d("1.x").to_string().contains("help: wildcards aren't supported"));
+  assert_eq!(Diagnostic::from_error(" ", &Version::parse(" ").unwrap_err()).explanation, "the input is empty");
+  assert!(Diagnostic::from_error("1.2.3-", &Version::parse("1.2.3-").unwrap_err()).explanation.starts_with("the input ended too early"));
+}
+
+#[test]
+fn lenient_operators() {
+  use crate::diagnostics::{Warning, WarningKind};
+  let lenient = |range: &str| Range::parse_lenient(range).unwrap_or_else(|_| panic!("Failed to parse range: {}", range));
+  let respelled = |written: &str, meant: &str, span: std::ops::Range<usize>| Warning {
+    kind: WarningKind::OperatorRespelled { written: written.to_string(), meant: meant.to_string() },
+    span,
+  };
+
+  assert!(Range::parse("=>1.2").is_err());
+  assert_eq!(lenient("=>1.2"), (r(">=1.2"), vec![respelled("=>", ">=", 0..2)]));
+  assert_eq!(lenient("=>1.2 =<2").0, r(">=1.2 <=2"));
+  assert_eq!(lenient("≥1.2 ≤2"), (r(">=1.2 <=2"), vec![respelled("≥", ">=", 0..3), respelled("≤", "<=", 7..10)]));
+  assert_eq!(lenient("~> 2.1"), (r(">=2.1 <3"), vec![respelled("~>", ">=2.1 <3", 0..2)]));
+  assert_eq!(lenient("~=1.4.5").0, r(">=1.4.5 <1.5"));
+  assert_eq!(lenient("~>2.1, !=2.1.3").0, r(">=2.1 <3 !=2.1.3"));
+  assert_eq!(lenient(">=1.2 <2"), (r(">=1.2 <2"), vec![]));
+  // the comparators are intersected, not merged into the loosest bounds
+  assert_eq!(lenient("=>1.5 ~>1.2").0, r(">=1.5 <2"));
+  assert_eq!(lenient("~> 2.1, < 2.5").0, r(">=2.1 <2.5"));
+  assert!(!lenient("~> 2.1, < 2.5").0.contains(v("2.9.0")));
+  assert!(Range::parse_lenient("=>").is_err());
+  assert_eq!(lenient("=>1").1[0].to_string(), "`=>` was read as `>=` (at 0..2)");
+  // errors point into the range as written, not as rewritten
+  let error = |range: &str| Range::parse_lenient(range).unwrap_err().location;
+  assert_eq!(error("≥1.2 ≤x").offset, 10);
+  assert_eq!(error("≥1.2 ≤x").column, 7);
+  assert_eq!(error("~> 2.1, x").offset, 8);
+  assert_eq!(error("≥x").offset, 3);
+}
+
+#[test]
+fn versions_in_text() {
+  let found = Version::find_all;
+
+  assert_eq!(found("MyTool version 3.4.1-beta (build 77)"), vec![(v("3.4.1-beta"), 15..25)]);
+  assert_eq!(found("git version 2.39.2 (Apple Git-143)"), vec![(Version::new(2, 39, 2), 12..18)]);
+  assert_eq!(found("Released v2 and v1.4.0, see 1.3."), vec![
+    (Version::new(2, 0, 0), 9..11),
+    (Version::new(1, 4, 0), 16..22),
+    (Version::new(1, 3, 0), 28..31),
+  ]);
+  assert_eq!(found("## [1.2.3+Build.5] - 2024-01-01"), vec![(v("1.2.3+build.5"), 4..17)]);
+  assert_eq!(found("rustc 1.75.0-nightly.2 (1.2.3.4)"), vec![(v("1.75.0-nightly2"), 6..22), (v("1.2.3.4"), 24..31)]);
+  assert_eq!(found("python3.11, x86_64, build 77, vim, version1.2, 1.2abc"), vec![]);
+  assert_eq!(found(""), vec![]);
+  assert_eq!(Version::extract_first("node v18.17.1\nnpm 9.6.7"), Some((Version::new(18, 17, 1), 5..13)));
+  assert_eq!(Version::extract_first("no versions here"), None);
+  // too long to store, skipped instead of overflowing
+  assert_eq!(found("tool 1.0.0-nightly20240101 ok"), vec![]);
+  assert_eq!(found("1.0.0-abcdefghijklmnopqrstuvwxyz0123456789 and 2.0"), vec![(Version::new(2, 0, 0), 47..50)]);
+  // IPs look like versions with an extra version
+  assert_eq!(found("listening on 192.168.1.1"), vec![(v("192.168.1.1"), 13..24)]);
+}
+
+#[test]
+fn git_tags() {
+  use crate::tags::{read_tags, TagParser, TagPattern};
+  let tags = TagParser::default();
+  let some = |component: Option<&str>, version: &str| Some((component.map(|c| c.to_string()), v(version)));
+
+  assert_eq!(tags.parse("v1.2.3"), some(None, "1.2.3"));
+  assert_eq!(tags.parse("1.2"), some(None, "1.2.0"));
+  assert_eq!(tags.parse("release-1.2.3"), some(None, "1.2.3"));
+  assert_eq!(tags.parse("myapp@1.2.3"), some(Some("myapp"), "1.2.3"));
+  assert_eq!(tags.parse("@scope/pkg@2.0.0-rc1"), some(Some("@scope/pkg"), "2.0.0-rc1"));
+  assert_eq!(tags.parse("myapp/v1.2.3"), some(Some("myapp"), "1.2.3"));
+  assert_eq!(tags.parse("tools/cli/1.0"), some(Some("tools/cli"), "1.0.0"));
+  assert_eq!(tags.parse("latest"), None);
+  assert_eq!(tags.parse("@1.2.3"), None);
+
+  let custom = TagParser::new(vec![TagPattern::parse("{component}-v{version}-final").unwrap()]);
+  assert_eq!(custom.parse("core-v3.1-final"), some(Some("core"), "3.1.0"));
+  assert_eq!(custom.parse("core-v3.1"), None);
+  assert_eq!(TagPattern::parse("{component}@{version}").unwrap().to_string(), "{component}@{version}");
+  assert!(TagPattern::parse("release").is_err());
+  let accented = TagPattern::parse("é{version}").unwrap();
+  assert_eq!(accented.to_string(), "é{version}");
+  assert_eq!(accented.matches("é1.2.0"), Some((None, Version::new(1, 2, 0))));
+  assert_eq!(TagPattern::parse("{component}→{version}").unwrap().matches("app→2.0"), Some((Some("app".to_string()), Version::new(2, 0, 0))));
+  assert!(TagPattern::parse("{component}{version}").is_err());
+  assert!(TagPattern::parse("{version}-{version}").is_err());
+
+  // a fake repository, with loose and packed tags
+  let repo = std::env::temp_dir().join(format!("yasemver-tags-{}", std::process::id()));
+  let refs = repo.join(".git").join("refs").join("tags");
+  std::fs::create_dir_all(refs.join("myapp")).unwrap();
+  for tag in ["v1.2.3", "v1.10.0", "myapp/v0.9.0", "nightly"] {
+    std::fs::write(refs.join(tag), "0000000000000000000000000000000000000000\n").unwrap();
+  }
+  std::fs::write(repo.join(".git").join("packed-refs"), [
+    "# pack-refs with: peeled fully-peeled sorted",
+    "1111111111111111111111111111111111111111 refs/heads/main",
+    "2222222222222222222222222222222222222222 refs/tags/v2.0.0-rc1",
+    "^3333333333333333333333333333333333333333",
+    "4444444444444444444444444444444444444444 refs/tags/myapp@1.0.0",
+    "5555555555555555555555555555555555555555 refs/tags/v1.2.3",
+  ].join("\n")).unwrap();
+  assert_eq!(read_tags(&repo).unwrap(), vec!["myapp/v0.9.0", "myapp@1.0.0", "nightly", "v1.10.0", "v1.2.3", "v2.0.0-rc1"]);
+  let latest = tags.latest_in_repo(&repo).unwrap();
+  assert_eq!(latest.len(), 2);
+  assert_eq!(latest[&None], ("v2.0.0-rc1".to_string(), v("2.0.0-rc1")));
+  assert_eq!(latest[&Some("myapp".to_string())], ("myapp@1.0.0".to_string(), v("1.0.0")));
+  assert!(read_tags(repo.join("nope")).is_err());
+
+  // a linked worktree, its gitdir points back to the main .git with commondir
+  let worktree = repo.join("worktree");
+  let worktree_git = repo.join(".git").join("worktrees").join("worktree");
+  std::fs::create_dir_all(&worktree).unwrap();
+  std::fs::create_dir_all(&worktree_git).unwrap();
+  std::fs::write(worktree.join(".git"), format!("gitdir: {}\n", worktree_git.display())).unwrap();
+  std::fs::write(worktree_git.join("commondir"), "../..\n").unwrap();
+  assert_eq!(read_tags(&worktree).unwrap(), read_tags(&repo).unwrap());
+  std::fs::remove_dir_all(&repo).unwrap();
+}
+
+#[test]
+fn resolving() {
+  use crate::resolve::{resolve, MemoryProvider};
+  use crate::Dependency;
+  let d = |name: &str, range: &str| Dependency { name: name.to_string(), range: r(range) };
+  let solved = |root: &[Dependency], provider: &MemoryProvider| resolve(root, provider)
+    .unwrap_or_else(|c| panic!("Failed to resolve: {}", c))
+    .into_iter().map(|(name, version)| format!("{} {}", name, version)).collect::<Vec<_>>();
+
+  let mut provider = MemoryProvider::new();
+  provider
+    .add("foo", v("1.0.0"), vec![])
+    .add("foo", v("1.1.0"), vec![d("bar", "^2")])
+    .add("foo", v("2.0.0"), vec![d("bar", "^3")])
+    .add("bar", v("1.0.0"), vec![])
+    .add("bar", v("2.0.0"), vec![])
+    .add("bar", v("2.5.0"), vec![])
+    .add("bar", v("3.0.0"), vec![]);
+  assert_eq!(solved(&[d("foo", "^1")], &provider), vec!["bar 2.5.0", "foo 1.1.0"]);
+  assert_eq!(solved(&[d("foo", "*")], &provider), vec!["bar 3.0.0", "foo 2.0.0"]);
+  // foo 2 and 1.1 need a bar that root doesn't allow, so it goes back to foo 1.0.0
+  assert_eq!(solved(&[d("foo", "*"), d("bar", "<2")], &provider), vec!["bar 1.0.0", "foo 1.0.0"]);
+  assert_eq!(solved(&[d("foo", "*"), d("bar", "~2.0")], &provider), vec!["bar 2.0.0", "foo 1.1.0"]);
+  assert_eq!(solved(&[], &provider), Vec::<String>::new());
+
+  // the picked bar has to be undone when a later package needs another one
+  let mut provider = MemoryProvider::new();
+  provider
+    .add("app", v("1.0.0"), vec![d("bar", "*"), d("foo", "*")])
+    .add("bar", v("1.2.0"), vec![])
+    .add("bar", v("1.5.0"), vec![])
+    .add("foo", v("1.0.0"), vec![d("bar", "<1.3")])
+    .add("foo", v("2.0.0"), vec![d("bar", "<1.0")]);
+  assert_eq!(solved(&[d("app", "^1")], &provider), vec!["app 1.0.0", "bar 1.2.0", "foo 1.0.0"]);
+
+  let mut provider = MemoryProvider::new();
+  provider
+    .add("foo", v("2.0.0"), vec![d("bar", "<1.0")])
+    .add("foo", v("2.1.0"), vec![d("bar", "<1.0")])
+    .add("bar", v("0.9.0"), vec![])
+    .add("bar", v("1.2.0"), vec![]);
+  let conflict = resolve(&[d("bar", "^1.2"), d("foo", "^2")], &provider).unwrap_err();
+  assert!(conflict.explanation.contains(&"foo 2.1.0 requires bar <1.0.0 but root requires bar ^1.2.0".to_string()), "{}", conflict);
+  assert_eq!(conflict.explanation.last().unwrap(), "so no version of foo fits, but root requires foo ^2.0.0");
+  let conflict = resolve(&[d("foo", "^3")], &provider).unwrap_err();
+  assert_eq!(conflict.to_string(), "root requires foo ^3.0.0 but no version of foo matches");
+  assert_eq!(resolve(&[d("baz", "*")], &provider).unwrap_err().to_string(), "baz doesn't exist");
+}
+
+#[test]
+fn lockfiles() {
+  use crate::lockfile::{Drift, Lockfile};
+  use crate::resolve::MemoryProvider;
+  use crate::Dependency;
+  let d = |name: &str, range: &str| Dependency { name: name.to_string(), range: r(range) };
+
+  let mut lockfile = Lockfile::new();
+  lockfile
+    .insert("foo", v("1.1.0"), r(">=1.0 <1.5"))
+    .insert("bar", v("2.0.0-rc1"), r("^2.0.0-rc1"))
+    .insert("baz", v("0.3.1"), r("*"));
+  let text = lockfile.to_string();
+  assert_eq!(text, [
+    "# yasemver lockfile, one package per line: name version range",
+    "bar 2.0.0-rc1 ^2.0.0-rc1",
+    "baz 0.3.1 *",
+    "foo 1.1.0 >=1.0.0,<1.5.0",
+    "",
+  ].join("\n"));
+  assert_eq!(Lockfile::parse(&text).unwrap(), lockfile);
+  assert_eq!(Lockfile::parse(&text).unwrap().to_string(), text);
+  assert_eq!(lockfile.get("foo").unwrap().version, v("1.1.0"));
+  // != and = are written after ^ and ~ too, so they're read back
+  let mut excepts = Lockfile::new();
+  excepts
+    .insert("foo", v("1.2.3"), r(">=1.0.0 <2.0.0 !=1.5.0"))
+    .insert("bar", v("1.2.3"), r("~1.2 !=1.2.1"))
+    .insert("baz", v("3.0.0"), r("^1.0 =3.0.0"));
+  let text = excepts.to_string();
+  assert_eq!(text.lines().skip(1).collect::<Vec<_>>(), vec!["bar 1.2.3 ~1.2.0,!=1.2.1", "baz 3.0.0 ^1.0.0,=3.0.0", "foo 1.2.3 ^1.0.0,!=1.5.0"]);
+  assert_eq!(Lockfile::parse(&text).unwrap(), excepts);
+  assert!(!Lockfile::parse(&text).unwrap().get("foo").unwrap().range.contains(v("1.5.0")));
+  assert!(Lockfile::parse("foo 1.0.0").is_err());
+  assert!(Lockfile::parse("foo 1.0.0 *\nfoo 1.1.0 *").is_err());
+  assert_eq!(Lockfile::parse("\n# comment\nfoo x *").unwrap_err().to_string(), "line 3: foo x * should be name version range");
+
+  // foo's range moved on and qux was added, baz isn't in the manifest so its own range is used
+  let manifest = [d("foo", "^1.2"), d("bar", "^2.0.0-rc1"), d("qux", "~3")];
+  assert_eq!(lockfile.check(&manifest), vec![
+    Drift::Unsatisfied { name: "foo".to_string(), locked: v("1.1.0"), range: r("^1.2") },
+    Drift::Missing { name: "qux".to_string(), range: r("~3") },
+  ]);
+  assert_eq!(lockfile.check(&manifest)[0].to_string(), "foo is locked at 1.1.0, which isn't in ^1.2.0");
+
+  let mut provider = MemoryProvider::new();
+  provider
+    .add("bar", v("2.0.0-rc1"), vec![])
+    .add("bar", v("2.0.0"), vec![])
+    .add("bar", v("3.0.0"), vec![])
+    .add("baz", v("0.3.1"), vec![])
+    .add("foo", v("1.4.0"), vec![]);
+  let drifts = lockfile.check_upgrades(&manifest, &provider);
+  assert_eq!(drifts.len(), 4);
+  assert_eq!(drifts[2], Drift::Upgradable { name: "bar".to_string(), locked: v("2.0.0-rc1"), latest: v("2.0.0") });
+  assert_eq!(drifts[3], Drift::Upgradable { name: "foo".to_string(), locked: v("1.1.0"), latest: v("1.4.0") });
+}
+
+#[test]
+fn outdated_packages() {
+  use crate::outdated::{outdated, Change, Upgrade};
+  use crate::resolve::MemoryProvider;
+  use crate::Dependency;
+  use std::collections::BTreeMap;
+  let d = |name: &str, range: &str| Dependency { name: name.to_string(), range: r(range) };
+  let up = |version: &str, change: Change| Some(Upgrade { version: v(version), change });
+
+  assert_eq!(Change::between(&v("1.2.3"), &v("2.0.0")), Some(Change::Major));
+  assert_eq!(Change::between(&v("1.2.3"), &v("1.3.0")), Some(Change::Minor));
+  assert_eq!(Change::between(&v("1.2.3"), &v("1.2.4")), Some(Change::Patch));
+  assert_eq!(Change::between(&v("1.2.3-rc1"), &v("1.2.3")), Some(Change::PreRelease));
+  assert_eq!(Change::between(&v("1.2.3"), &v("1.2.3")), None);
+  assert_eq!(Change::between(&v("1.2.3"), &v("1.0.0")), None);
+
+  let mut provider = MemoryProvider::new();
+  for version in ["1.1.0", "1.1.5", "1.4.0", "1.9.0", "2.1.0", "3.0.0-beta"] {
+    provider.add("foo", v(version), vec![]);
+  }
+  for version in ["0.9.0", "1.0.0"] {
+    provider.add("bar", v(version), vec![]);
+  }
+  let current: BTreeMap<String, Version> = [("foo", "1.1.0"), ("bar", "1.0.0")].iter()
+    .map(|(name, version)| (name.to_string(), v(version)))
+    .collect();
+  let report = outdated(&current, &[d("foo", "~1.1"), d("bar", "^1"), d("baz", "*")], &provider);
+  assert_eq!(report.len(), 2);
+  assert_eq!(report[0].wanted, up("1.1.5", Change::Patch));
+  assert_eq!(report[0].compatible, up("1.9.0", Change::Minor));
+  assert_eq!(report[0].latest, up("2.1.0", Change::Major));
+  assert!(report[0].is_outdated());
+  assert_eq!(report[0].to_string(), "foo 1.1.0 (~1.1.0): wanted 1.1.5 (patch), compatible 1.9.0 (minor), latest 2.1.0 (major)");
+  assert!(!report[1].is_outdated());
+  assert_eq!(report[1].to_string(), "bar 1.0.0 (^1.0.0): up to date");
+
+  // pre-releases only count when already on one
+  let current: BTreeMap<String, Version> = [("foo".to_string(), v("2.1.0-rc1"))].into_iter().collect();
+  assert_eq!(outdated(&current, &[d("foo", "*")], &provider)[0].latest, up("3.0.0-beta", Change::Major));
+}
+
+#[test]
+fn widening_ranges() {
+  use crate::WidenStyle::{Alternative, Bound, Bump};
+  let widen = |range: &str, version: &str, style| r(range).widen_to_include(&v(version), style).to_string();
+
+  assert_eq!(widen("^1.2", "2.0.0", Alternative), "^1.2.0 || ^2.0.0");
+  assert_eq!(widen("^1.2", "2.0.0", Bound), ">=1.2.0,<3.0.0");
+  assert_eq!(widen("^1.2", "2.0.0", Bump), "^2.0.0");
+  assert_eq!(widen("^1.2", "2.0.0+linux", Bump), "^2.0.0");
+  // already in range
+  assert_eq!(widen("^1.2", "1.5.0", Bump), "^1.2.0");
+  assert!(r("^1.2").widen_to_include(&v("2.1.0"), Alternative).contains(v("2.1.0")));
+
+  assert_eq!(widen("~1.2", "1.3.4", Bound), ">=1.2.0,<1.4.0");
+  assert_eq!(widen("~1.2", "1.3.4", Bump), "~1.3.4");
+  assert_eq!(widen(">=1.2 <1.5", "0.9.0", Bound), ">=0.9.0,<1.5.0");
+  assert_eq!(widen(">=1.2 <1.5", "0.9.0", Bump), ">=0.9.0,<1.5.0");
+  assert_eq!(widen(">=1.0 !=1.2.3", "1.2.3", Bound), ">=1.0.0");
+  assert_eq!(widen("=1.2.3", "1.3.0", Bump), "=1.3.0");
+  assert_eq!(widen("=1.2.3", "1.3.0", Bound), "=1.2.3,=1.3.0");
+  // what's printed is what's returned, excepts included
+  let widened = r("^1.2 !=1.3.0").widen_to_include(&v("2.0.0"), Alternative);
+  assert_eq!(widened.to_string(), "^1.2.0,!=1.3.0 || ^2.0.0");
+  assert!(!widened.contains(v("1.3.0")));
+  assert_eq!(r("^1.2.0,!=1.3.0"), widened.ranges[0]);
+  assert_eq!(widen("~1.2 !=1.2.5", "1.3.4", Bound), ">=1.2.0,<1.4.0,!=1.2.5");
+}
+
+#[test]
+fn narrowing_ranges() {
+  assert_eq!(r(">=1.2 !=1.3.0").pin(&v("1.4.0+linux")), Some(r("=1.4.0")));
+  assert!(!r("*").pin(&v("1.4.0")).unwrap().contains(v("1.4.1")));
+  // only versions the range allows
+  assert_eq!(r("^1.2.0 !=1.3.0").pin(&v("1.3.0")), None);
+  assert_eq!(r("^1.2.0").pin(&v("5.0.0")), None);
+
+  assert_eq!(r(">=1.2").tighten_to_tilde().to_string(), "~1.2.0");
+  assert_eq!(r("^1.2.3").tighten_to_tilde().to_string(), "~1.2.3");
+  assert_eq!(r(">=1.2 <1.2.5").tighten_to_tilde(), r(">=1.2 <1.2.5"));
+  assert_eq!(r(">=1.2").tighten_to_caret().to_string(), "^1.2.0");
+  assert_eq!(r("<3").tighten_to_caret().to_string(), "^0.0.0");
+  assert_eq!(r("=1.2.3").tighten_to_caret(), r("=1.2.3"));
+  // excepts that are still in range stay
+  assert_eq!(r(">=1.2 !=1.2.4 !=1.5.0").tighten_to_tilde(), r("~1.2 !=1.2.4"));
+
+  assert_eq!(r("^1.2").raise_minimum(&v("1.4.0")).unwrap().to_string(), "^1.4.0");
+  assert_eq!(r(">=1.2 <3").raise_minimum(&v("2.1.0")), Some(r(">=2.1.0 <3")));
+  assert_eq!(r(">=1.5").raise_minimum(&v("1.2.0")), Some(r(">=1.5")));
+  assert_eq!(r(">=1.2 !=1.3.0 !=1.6.0").raise_minimum(&v("1.4.0")), Some(r(">=1.4 !=1.6.0")));
+  assert_eq!(r("=1.2.3").raise_minimum(&v("1.0.0")), Some(r("=1.2.3")));
+  // at or above the max nothing is left
+  assert_eq!(r("^1.2").raise_minimum(&v("2.0.0")), None);
+  assert_eq!(r("^1.2").raise_minimum(&v("3.1.0")), None);
+  assert_eq!(r("=1.2.3").raise_minimum(&v("1.3.0")), None);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn cargo_manifests() {
+  use crate::cargo::{parse_requirement, CargoError, Manifest, Table};
+  let cr = |requirement: &str| parse_requirement(requirement).unwrap();
+  assert_eq!(cr("1.2"), r(">=1.2 <2"));
+  assert_eq!(cr("^0.3"), r(">=0.3 <0.4"));
+  assert_eq!(cr("0.0.3"), r(">=0.0.3 <0.0.4"));
+  assert_eq!(cr("~1.2.3"), r(">=1.2.3 <1.3"));
+  assert_eq!(cr("~1"), r(">=1 <2"));
+  assert_eq!(cr("=1.2"), r(">=1.2 <1.3"));
+  assert_eq!(cr("=1.2.3"), r("=1.2.3"));
+  assert_eq!(cr(">=1.2, <1.5"), r(">=1.2 <1.5"));
+  assert_eq!(cr("1.*"), r(">=1 <2"));
+  assert_eq!(cr("*"), r("*"));
+  assert!(parse_requirement("1.2 || 2").is_err());
+  // too long for a Version once the dots are dropped
+  assert!(parse_requirement("1.0.0-alpha.20240101").is_err());
+  assert!(parse_requirement("~1.0.0-alpha.20240101").is_err());
+  // nothing comes after 4294967295
+  assert!(parse_requirement("^4294967295").is_err());
+  assert!(parse_requirement("=1.4294967295").is_err());
+  assert!(parse_requirement("~4294967295.4294967295.0").is_err());
+  assert_eq!(cr(">=4294967295"), r(">=4294967295"));
+
+  let manifest = r#"[package]
+name = "app"
+
+[dependencies]
+serde = "1.0" # pinned by the framework
+log = { version = "0.4.14", features = ["std"] }
+json = { package = "serde_json", version = "~1.0.100" }
+local = { path = "../local" }
+common = { workspace = true }
+
+[dev_dependencies.criterion]
+version = ">=0.4, <0.6"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+
+[workspace.dependencies]
+common = "2"
+"#;
+  let mut manifest = Manifest::parse(manifest).unwrap();
+  let dependencies = manifest.dependencies().unwrap();
+  let found: Vec<(&str, &str, Table, Option<&str>, String)> = dependencies.iter()
+    .map(|d| (d.key.as_str(), d.dependency.name.as_str(), d.table, d.target.as_deref(), d.dependency.range.to_string()))
+    .collect();
+  assert_eq!(found, vec![
+    ("serde", "serde", Table::Dependencies, None, "^1.0.0".to_string()),
+    ("log", "log", Table::Dependencies, None, "~0.4.14".to_string()),
+    ("json", "serde_json", Table::Dependencies, None, "~1.0.100".to_string()),
+    ("criterion", "criterion", Table::DevDependencies, None, ">=0.4.0,<0.6.0".to_string()),
+    ("winapi", "winapi", Table::Dependencies, Some("cfg(windows)"), "~0.3.0".to_string()),
+    ("common", "common", Table::Workspace, None, "^2.0.0".to_string()),
+  ]);
+
+  manifest.set_requirement(&dependencies[0], &r("^1.2")).unwrap();
+  manifest.set_requirement(&dependencies[1], &r(">=0.4.20 <0.5")).unwrap();
+  manifest.set_requirement(&dependencies[3], &r(">=0.5 <0.7")).unwrap();
+  manifest.set_requirement(&dependencies[4], &r("=0.3.9")).unwrap();
+  manifest.set_requirement(&dependencies[5], &r("^3")).unwrap();
+  assert_eq!(manifest.set_requirement(&dependencies[2], &r(">=1 !=1.0.5")), Err(CargoError::Unrepresentable(">=1.0.0,!=1.0.5".to_string())));
+  assert_eq!(manifest.to_string(), r#"[package]
+name = "app"
+
+[dependencies]
+serde = "1.2.0" # pinned by the framework
+log = { version = "0.4.20", features = ["std"] }
+json = { package = "serde_json", version = "~1.0.100" }
+local = { path = "../local" }
+common = { workspace = true }
+
+[dev_dependencies.criterion]
+version = ">=0.5.0, <0.7.0"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "=0.3.9"
+
+[workspace.dependencies]
+common = "3.0.0"
+"#);
+  assert_eq!(Manifest::parse("[dependencies]\nfoo = \"1.2 || 2\"").unwrap().dependencies(),
+    Err(CargoError::InvalidRequirement { name: "foo".to_string(), requirement: "1.2 || 2".to_string() }));
+  let mut big = Manifest::parse("[dependencies]\nfoo = \"1\"").unwrap();
+  let foo = big.dependencies().unwrap().remove(0);
+  big.set_requirement(&foo, &r(">=4294967295.0.0 <4294967295.1.0")).unwrap();
+  assert_eq!(big.to_string(), "[dependencies]\nfoo = \"~4294967295.0.0\"\n");
+  assert_eq!(Manifest::parse("[dependencies]\nfoo = \"=1.0.0-alpha.20240101\"").unwrap().dependencies(),
+    Err(CargoError::InvalidRequirement { name: "foo".to_string(), requirement: "=1.0.0-alpha.20240101".to_string() }));
+}
+
+#[test]
+fn package_json() {
+  use crate::npm::{parse_range, NpmError, PackageJson, Section, Spec};
+  use crate::RangeSet;
+  let nr = |range: &str| parse_range(range).unwrap();
+  let rs = |ranges: &[&str]| RangeSet::new(ranges.iter().map(|range| r(range)).collect());
+  assert_eq!(nr("^1.2"), rs(&["^1.2"]));
+  assert_eq!(nr("^0.3.1"), rs(&[">=0.3.1 <0.4"]));
+  assert_eq!(nr("^0.0.3"), rs(&[">=0.0.3 <0.0.4"]));
+  assert_eq!(nr("~1.2.3"), rs(&["~1.2.3"]));
+  assert_eq!(nr("1.x || >=2.5.0 <3"), rs(&[">=1 <2", ">=2.5 <3"]));
+  assert_eq!(nr("1.2 - 2.3.4"), rs(&[">=1.2 <2.3.5"]));
+  assert_eq!(nr("1.2.3 - 2.3"), rs(&[">=1.2.3 <2.4"]));
+  assert_eq!(nr("1.2.3"), rs(&["=1.2.3"]));
+  assert_eq!(nr(">1.2"), rs(&[">=1.3"]));
+  assert_eq!(nr(""), rs(&["*"]));
+  assert_eq!(nr("*"), rs(&["*"]));
+
+  assert_eq!(Spec::parse("latest"), Some(Spec::Tag("latest".to_string())));
+  assert_eq!(Spec::parse("npm:@scope/pkg@^2"), Some(Spec::Alias { package: "@scope/pkg".to_string(), range: nr("^2") }));
+  assert_eq!(Spec::parse("workspace:^"), Some(Spec::Workspace("^".to_string())));
+  assert_eq!(Spec::parse("file:../local"), Some(Spec::File("file:../local".to_string())));
+  assert_eq!(Spec::parse("user/repo#v1.0.0"), Some(Spec::Git("user/repo#v1.0.0".to_string())));
+  assert_eq!(Spec::parse("git+https://github.com/user/repo.git"), Some(Spec::Git("git+https://github.com/user/repo.git".to_string())));
+  assert_eq!(Spec::parse("https://example.com/pkg.tgz"), Some(Spec::Url("https://example.com/pkg.tgz".to_string())));
+  assert_eq!(Spec::parse("not a range"), None);
+
+  let text = r#"{
+  "name": "app",
+  "version": "1.0.0",
+  "dependencies": {
+    "react": "^18.2.0",
+    "lodash": "4.17.21",
+    "width": "npm:string-width@^4.2.0",
+    "shared": "workspace:^",
+    "local": "file:../local"
+  },
+  "devDependencies": {
+    "typescript": "~5.1 || ^5.3",
+    "eslint": "latest"
+  },
+  "peerDependencies": { "react-dom": ">=17" }
+}
+"#;
+  let mut package = PackageJson::parse(text).unwrap();
+  let names: Vec<(&str, Section)> = package.dependencies().iter().map(|d| (d.name.as_str(), d.section)).collect();
+  assert_eq!(names, vec![
+    ("react", Section::Dependencies), ("lodash", Section::Dependencies), ("width", Section::Dependencies),
+    ("shared", Section::Dependencies), ("local", Section::Dependencies),
+    ("typescript", Section::DevDependencies), ("eslint", Section::DevDependencies), ("react-dom", Section::PeerDependencies),
+  ]);
+  let dependency = |package: &PackageJson, section, name: &str| package.get(section, name).unwrap().clone();
+  assert_eq!(dependency(&package, Section::Dependencies, "width").to_dependency().unwrap().name, "string-width");
+  assert_eq!(dependency(&package, Section::DevDependencies, "typescript").to_dependency(), None);
+
+  let set = |package: &mut PackageJson, section, name: &str, range: &str| {
+    let d = dependency(package, section, name);
+    package.set_range(&d, &nr(range))
+  };
+  set(&mut package, Section::Dependencies, "react", "^19").unwrap();
+  set(&mut package, Section::Dependencies, "lodash", "4.17.22").unwrap();
+  set(&mut package, Section::Dependencies, "width", "^5.0.0").unwrap();
+  set(&mut package, Section::Dependencies, "shared", "^1.2").unwrap();
+  set(&mut package, Section::DevDependencies, "typescript", "~5.1 || ^5.3 || 6.0.x").unwrap();
+  set(&mut package, Section::DevDependencies, "eslint", "^9").unwrap();
+  set(&mut package, Section::PeerDependencies, "react-dom", ">=17 <20").unwrap();
+  assert_eq!(set(&mut package, Section::Dependencies, "local", "^1"), Err(NpmError::NotARange("local".to_string())));
+  // the biggest numbers don't overflow when the range is written back
+  let react = dependency(&package, Section::Dependencies, "react");
+  package.set_range(&react, &rs(&[">=4294967295.0.0 <4294967295.1.0"])).unwrap();
+  assert_eq!(package.get(Section::Dependencies, "react").unwrap().text, "~4294967295.0.0");
+  package.set_range(&react, &rs(&["^19"])).unwrap();
+  assert_eq!(package.to_string(), r#"{
+  "name": "app",
+  "version": "1.0.0",
+  "dependencies": {
+    "react": "^19.0.0",
+    "lodash": "4.17.22",
+    "width": "npm:string-width@^5.0.0",
+    "shared": "workspace:^1.2.0",
+    "local": "file:../local"
+  },
+  "devDependencies": {
+    "typescript": "~5.1.0 || ^5.3.0 || ~6.0.0",
+    "eslint": "^9.0.0"
+  },
+  "peerDependencies": { "react-dom": ">=17.0.0 <20.0.0" }
+}
+"#);
+  assert_eq!(PackageJson::parse(r#"{"dependencies": {"a": 1}}"#), Err(NpmError::InvalidSpec { name: "a".to_string(), spec: "1".to_string() }));
+  assert!(matches!(PackageJson::parse(r#"{"dependencies": {"#), Err(NpmError::Json(_))));
+  assert!(parse_range("1.0.0-alpha.20240101").is_err());
+  assert!(parse_range("^1.0.0-alpha.20240101 || 2").is_err());
+  assert!(parse_range("1.0.0-alpha.20240101 - 2").is_err());
+  assert_eq!(Spec::parse("1.0.0-alpha.20240101"), None);
+  // nothing comes after 4294967295
+  assert!(parse_range("^4294967295").is_err());
+  assert!(parse_range("1.4294967295").is_err());
+  assert!(parse_range(">4294967295").is_err());
+  assert_eq!(nr(">=4294967295"), rs(&[">=4294967295"]));
+  assert_eq!(PackageJson::parse(r#"{"dependencies": {"a": "1.0.0-alpha.20240101"}}"#),
+    Err(NpmError::InvalidSpec { name: "a".to_string(), spec: "1.0.0-alpha.20240101".to_string() }));
+}
+
+#[test]
+fn python_requirements() {
+  use crate::pep508::parse_poetry_constraint;
+  use crate::python::{read_requirements_txt, LineError};
+  let pr = |constraint: &str| parse_poetry_constraint(constraint).unwrap();
+  assert_eq!(pr("^1.2"), r(">=1.2 <2"));
+  assert_eq!(pr("^0.3.1"), r(">=0.3.1 <0.4"));
+  assert_eq!(pr("~1.2.3"), r(">=1.2.3 <1.3"));
+  assert_eq!(pr("~1"), r(">=1 <2"));
+  assert_eq!(pr("~=1.2"), r(">=1.2 <2"));
+  assert_eq!(pr("1.2.3"), r("=1.2.3"));
+  assert_eq!(pr("1.2.*"), r(">=1.2 <1.3"));
+  assert_eq!(pr(">=1.2,<1.5"), r(">=1.2 <1.5"));
+  assert_eq!(pr(">= 1.2 < 1.5"), r(">=1.2 <1.5"));
+  assert_eq!(pr("*"), r("*"));
+  assert!(parse_poetry_constraint("^1.2.*").is_err());
+
+  let dir = std::env::temp_dir().join(format!("yasemver-python-{}", std::process::id()));
+  std::fs::create_dir_all(dir.join("requirements")).unwrap();
+  std::fs::write(dir.join("requirements").join("base.txt"), "# shared\nrequests[socks]>=2.28 ; python_version >= \"3.8\"\n-r ../requirements.txt\n").unwrap();
+  std::fs::write(dir.join("requirements.txt"), [
+    "-r requirements/base.txt",
+    "--index-url https://pypi.example.com/simple",
+    "django==4.2.* \\",
+    "    --hash=sha256:0000000000000000000000000000000000000000000000000000000000000000",
+    "flask~=2.3  # web",
+    "-e git+https://github.com/user/repo.git#egg=repo",
+    "./vendored/pkg",
+    "numpy>=",
+    "--requirement missing.txt",
+  ].join("\n")).unwrap();
+  let extracted = read_requirements_txt(dir.join("requirements.txt")).unwrap();
+  let dependencies: Vec<(String, String)> = extracted.dependencies().iter().map(|d| (d.name.clone(), d.range.to_string())).collect();
+  assert_eq!(dependencies, vec![
+    ("requests".to_string(), ">=2.28.0".to_string()),
+    ("django".to_string(), "~4.2.0".to_string()),
+    ("flask".to_string(), "^2.3.0".to_string()),
+  ]);
+  assert_eq!(extracted.requirements[0].extras, vec!["socks"]);
+  assert_eq!(extracted.errors.len(), 2);
+  assert_eq!((extracted.errors[0].line, extracted.errors[0].content.as_str()), (8, "numpy>="));
+  assert_eq!(extracted.errors[0].file, dir.join("requirements.txt"));
+  assert_eq!((extracted.errors[1].line, extracted.errors[1].content.as_str()), (9, "--requirement missing.txt"));
+  let error = LineError { file: "requirements.txt".into(), line: 3, content: "numpy>=".to_string(), message: "invalid requirement".to_string() };
+  assert_eq!(error.to_string(), "requirements.txt:3: invalid requirement: numpy>=");
+  assert!(read_requirements_txt(dir.join("nope.txt")).is_err());
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn pyproject_requirements() {
+  use crate::python::parse_pyproject;
+  let pyproject = r#"[project]
+name = "app"
+dependencies = [
+  "httpx>=0.24",
+  "attrs[tests]==23.*",
+  "broken>=",
+]
+
+[project.optional-dependencies]
+cli = ["click~=8.1"]
+
+[tool.poetry.dependencies]
+python = "^3.8"
+pydantic = "^2.1"
+uvicorn = { version = "~0.23", extras = ["standard"], markers = "sys_platform != 'win32'" }
+numpy = [
+  { version = "^1.24", python = ">=3.9" },
+  { version = "^1.21", python = "<3.9" },
+]
+mylib = { path = "../mylib" }
+
+[tool.poetry.group.dev.dependencies]
+pytest = "7.4.0"
+black = "^23 || ^24"
+"#;
+  let extracted = parse_pyproject(pyproject, "pyproject.toml");
+  let dependencies: Vec<(String, String)> = extracted.dependencies().iter().map(|d| (d.name.clone(), d.range.to_string())).collect();
+  assert_eq!(dependencies, vec![
+    ("httpx".to_string(), ">=0.24.0".to_string()),
+    ("attrs".to_string(), "^23.0.0".to_string()),
+    ("click".to_string(), "^8.1.0".to_string()),
+    ("pydantic".to_string(), "^2.1.0".to_string()),
+    ("uvicorn".to_string(), "~0.23.0".to_string()),
+    ("numpy".to_string(), "^1.24.0".to_string()),
+    ("numpy".to_string(), "^1.21.0".to_string()),
+    ("pytest".to_string(), "=7.4.0".to_string()),
+  ]);
+  assert_eq!(extracted.requirements[4].extras, vec!["standard"]);
+  assert!(extracted.requirements[4].marker.is_some());
+  let errors: Vec<(usize, &str)> = extracted.errors.iter().map(|e| (e.line, e.content.as_str())).collect();
+  assert_eq!(errors, vec![(6, "broken>="), (24, "^23 || ^24")]);
+
+  let broken = parse_pyproject("[project]\ndependencies = [\n", "pyproject.toml");
+  assert_eq!(broken.errors.len(), 1);
+  assert_eq!(broken.errors[0].line, 3);
+}
+
+// runs the command line with input as stdin, returns the exit code, stdout and stderr
+fn yasemver(args: &[&str], input: &str) -> (u8, String, String) {
+  let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+  let (mut output, mut errors) = (vec![], vec![]);
+  let code = crate::cli::run(&args, input.as_bytes(), &mut output, &mut errors);
+  (code, String::from_utf8(output).unwrap(), String::from_utf8(errors).unwrap())
+}
+
+#[test]
+fn command_line() {
+  let out = |code: u8, output: &str| (code, output.to_string(), String::new());
+  assert_eq!(yasemver(&["parse", "1.2.3.4-RC1+win", "v2"], ""), out(0, concat!(
+    "{\"version\":\"1.2.3.4-rc1+win\",\"major\":1,\"minor\":2,\"patch\":3,\"extra_version\":\"4\",\"pre_release\":\"rc1\",\"build\":\"win\"}\n",
+    "{\"version\":\"2.0.0\",\"major\":2,\"minor\":0,\"patch\":0,\"extra_version\":null,\"pre_release\":null,\"build\":null}\n",
+  )));
+  assert_eq!(yasemver(&["compare", "1.0.0-rc1", "1.0.0"], ""), out(0, "-1\n"));
+  assert_eq!(yasemver(&["compare", "1.2", "v1.2.0"], ""), out(0, "0\n"));
+  assert_eq!(yasemver(&["satisfies", "^1.2", "1.3.0", "1.9.9"], ""), out(0, "1.3.0\n1.9.9\n"));
+  assert_eq!(yasemver(&["satisfies", "^1.2", "1.3.0", "2.0.0"], ""), out(1, "1.3.0\n"));
+  assert_eq!(yasemver(&["sort"], "1.10.0\nv1.2\n\n2.0.0-rc1\n1.2.0\n"), out(0, "v1.2\n1.2.0\n1.10.0\n2.0.0-rc1\n"));
+  assert_eq!(yasemver(&["sort", "--unique", "--reverse"], "1.10.0\nv1.2\n1.2.0\n"), out(0, "1.10.0\nv1.2\n"));
+  assert_eq!(yasemver(&["sort"], "1.0.0-rc10\n1.0.0-rc9\n"), out(0, "1.0.0-rc9\n1.0.0-rc10\n"));
+  assert_eq!(yasemver(&["max-satisfying", "<1.5", "1.10.0", "1.4.2", "1.2.0"], ""), out(0, "1.4.2\n"));
+  assert_eq!(yasemver(&["max-satisfying", ">=3"], "1.10.0\n2.0.0\n"), out(1, ""));
+  assert_eq!(yasemver(&["bump", "minor", "1.2.3"], ""), out(0, "1.3.0\n"));
+  assert_eq!(yasemver(&["bump", "major", "2.0.0-rc1"], ""), out(0, "2.0.0\n"));
+  assert_eq!(yasemver(&["bump", "patch", "1.2.3-rc1"], ""), out(0, "1.2.3\n"));
+  assert_eq!(yasemver(&["diff", "2.0.0", "1.2.3"], ""), out(0, "major\n"));
+  assert_eq!(yasemver(&["diff", "1.2.3-rc1", "1.2.3"], ""), out(0, "pre-release\n"));
+  assert_eq!(yasemver(&["diff", "1.0", "1.0.0"], ""), out(1, ""));
+  assert_eq!(yasemver(&["normalize-range", "=>1.2 <2"], ""), out(0, "^1.2.0\n"));
+  assert_eq!(yasemver(&["normalize-range", "=>1.5 ~>1.2"], ""), out(0, "^1.5.0\n"));
+
+  // wrong arguments and input
+  let (code, output, errors) = yasemver(&["compare", "1", "x"], "");
+  assert_eq!((code, output.as_str()), (2, ""));
+  assert_eq!(errors, "yasemver: invalid version\nx\n^ unexpected `x`, expected a space, a number or v\n");
+  assert_eq!(yasemver(&["sort"], "1.0\nnope\n").2.lines().next(), Some("yasemver: line 2: invalid version"));
+  assert_eq!(yasemver(&["sort", "--bogus"], ""), (2, String::new(), "yasemver: unknown option --bogus\n".to_string()));
+  assert_eq!(yasemver(&["bump", "build", "1.0.0"], "").0, 2);
+  assert_eq!(yasemver(&["bump", "major", "4294967295.0.0"], ""), (2, String::new(), "yasemver: can't bump the major of 4294967295.0.0, it's already 4294967295\n".to_string()));
+  assert_eq!(yasemver(&["bump", "patch", "1.2.4294967295"], "").0, 2);
+  assert_eq!(yasemver(&["bump", "major", "4294967295.0.0-rc1"], ""), out(0, "4294967295.0.0\n"));
+  assert_eq!(yasemver(&["compare", "1.0.0"], "").0, 2);
+  // pre-releases that can't be stored are invalid versions, not a crash
+  let (code, output, errors) = yasemver(&["parse", "1.0.0-rc.1"], "");
+  assert_eq!((code, output.as_str()), (2, ""));
+  assert!(errors.starts_with("yasemver: invalid version\n1.0.0-rc.1\n"));
+  assert!(errors.trim_end().ends_with("an extra version and pre-release of at most 12 letters and digits"));
+  assert_eq!(yasemver(&["compare", "1.0.0", "1.0.0-abcdefghijklm"], "").0, 2);
+  assert_eq!(yasemver(&["satisfies", "^1", "1.0.0-rc.1"], "").0, 2);
+  assert_eq!(yasemver(&["max-satisfying", "^1", "1.0.0-rc.1"], "").0, 2);
+  assert_eq!(yasemver(&["sort"], "1.0\n1.0.0-rc.1\n").2.lines().next(), Some("yasemver: line 2: invalid version"));
+  assert_eq!(yasemver(&["frobnicate"], "").0, 2);
+  assert_eq!(yasemver(&[], "").0, 2);
+}
+
+#[test]
+fn command_line_filter() {
+  let versions = "1.2.0\nfoo\n\n1.3.0-rc1\n2.0.0\nv1.4\n";
+  let out = |code: u8, output: &str| (code, output.to_string(), String::new());
+  assert_eq!(yasemver(&["filter", "^1"], versions), out(0, "1.2.0\nv1.4\n"));
+  assert_eq!(yasemver(&["filter", "^1", "--prerelease"], versions), out(0, "1.2.0\n1.3.0-rc1\nv1.4\n"));
+  assert_eq!(yasemver(&["filter", "^1", "--prerelease=only"], versions), out(0, "1.3.0-rc1\n"));
+  assert_eq!(yasemver(&["filter", "^1", "--prerelease=exclude", "--invalid=pass"], versions), out(0, "1.2.0\nfoo\nv1.4\n"));
+  assert_eq!(yasemver(&["filter", ">=3"], versions), out(1, ""));
+  // invalid lines don't count as matches
+  assert_eq!(yasemver(&["filter", ">=3", "--invalid=pass"], versions), out(1, "foo\n"));
+
+  // what matched before the invalid line is already out
+  let (code, output, errors) = yasemver(&["filter", "^1", "--invalid=error"], versions);
+  assert_eq!((code, output.as_str()), (2, "1.2.0\n"));
+  assert!(errors.starts_with("yasemver: line 2: invalid version\nfoo\n"));
+
+  // versions a Version can't store go through --invalid like any other invalid line
+  let unstorable = "1.1.0\n1.2.0-rc.1\n1.3.0-abcdefghijklm\n1.4.0\n";
+  assert_eq!(yasemver(&["filter", "^1", "--prerelease"], unstorable), out(0, "1.1.0\n1.4.0\n"));
+  assert_eq!(yasemver(&["filter", "^1", "--prerelease", "--invalid=pass"], unstorable), out(0, unstorable));
+  let (code, output, errors) = yasemver(&["filter", "^1", "--invalid=error"], unstorable);
+  assert_eq!((code, output.as_str()), (2, "1.1.0\n"));
+  assert!(errors.starts_with("yasemver: line 2: invalid version\n1.2.0-rc.1\n"));
+  let (code, output, errors) = yasemver(&["filter", "^1", "--invalid=error"], "1.3.0-abcdefghijklm\n");
+  assert_eq!((code, output.as_str()), (2, ""));
+  assert!(errors.starts_with("yasemver: line 1: invalid version\n1.3.0-abcdefghijklm\n"));
+
+  assert_eq!(yasemver(&["filter", "^1", "--invalid=maybe"], "").2, "yasemver: --invalid is skip, error or pass, not maybe\n");
+  assert_eq!(yasemver(&["filter", "^1", "--prerelease=some"], "").0, 2);
+  assert_eq!(yasemver(&["filter"], "").0, 2);
+
+  // a reader that never ends, filter has to write as it reads
+  struct Endless(u32);
+  impl std::io::Read for Endless {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+      self.0 += 1;
+      let line = format!("{}.0.0\n", self.0);
+      buf[..line.len()].copy_from_slice(line.as_bytes());
+      Ok(line.len())
+    }
+  }
+  // stops at the first write that fails, like a closed pipe
+  struct Head(usize, Vec<u8>);
+  impl std::io::Write for Head {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      if self.1.iter().filter(|b| **b == b'\n').count() >= self.0 {
+        return Err(std::io::ErrorKind::BrokenPipe.into());
+      }
+      self.1.extend_from_slice(buf);
+      Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+  let mut head = Head(2, vec![]);
+  let code = crate::cli::run(&["filter".to_string(), ">=5".to_string()], std::io::BufReader::new(Endless(0)), &mut head, &mut vec![]);
+  assert_eq!((code, String::from_utf8(head.1).unwrap()), (0, "5.0.0\n6.0.0\n".to_string()));
+}
@@ -0,0 +1,62 @@
+use crate::implementations::{Op, Range, Version};
+
+// a version as written in a Cargo, npm or Composer requirement,
+// how many numbers there were changes what ^, ~ and = mean
+pub(crate) struct Written {
+  pub numbers: Vec<u32>,
+  // Cargo's 1.2.*, the numbers are [1, 2]
+  pub wildcard: bool,
+  pub pre: Option<String>,
+}
+
+impl Written {
+  pub fn new(numbers: Vec<u32>, pre: Option<String>) -> Self {
+    Self { numbers, wildcard: false, pre }
+  }
+
+  // a 4th number (Composer's 1.2.3.4) is the extra_version
+  pub fn to_version(&self) -> Result<Version, &'static str> {
+    let number = |i: usize| self.numbers.get(i).copied().unwrap_or(0);
+    let extra = self.numbers.get(3).map(|n| n.to_string());
+    Version::new_w_extra(number(0), number(1), number(2), extra, self.pre.clone(), None)
+      .or(Err("a pre-release of at most 12 letters and digits"))
+  }
+
+  // 1.2 or 1.2.* stand for every 1.2.z
+  pub fn is_partial(&self) -> bool {
+    self.numbers.len() < 3 || self.wildcard
+  }
+
+  // 1.2 -> 1.3.0, the next version that doesn't start with the written numbers
+  pub fn next(numbers: &[u32]) -> Result<Version, &'static str> {
+    let mut numbers = numbers.to_vec();
+    if let Some(last) = numbers.last_mut() {
+      *last = last.checked_add(1).ok_or("a number below 4294967295")?;
+    }
+    Self::new(numbers, None).to_version()
+  }
+
+  pub fn between(&self, max: Version) -> Result<Range, &'static str> {
+    Ok(Range::from_ver_vec(vec![(Op::Ge, self.to_version()?), (Op::Lt, max)]))
+  }
+
+  // ^ keeps the first number that isn't 0: ^1.2.3 -> <2.0.0, ^0.3 -> <0.4.0, ^0.0.3 -> <0.0.4, ^0.0 -> <0.1.0
+  pub fn caret(&self) -> Result<Range, &'static str> {
+    let keep = self.numbers.iter().position(|n| *n != 0).map_or(self.numbers.len(), |i| i + 1).min(3);
+    self.between(Self::next(&self.numbers[..keep])?)
+  }
+}
+
+// the upper bound of ^min, to write a Range back as ^. None if there's none (^4294967295.0.0)
+pub(crate) fn caret_max(min: &Version) -> Option<Version> {
+  Some(match (min.major, min.minor) {
+    (0, 0) => Version::new(0, 0, min.patch.checked_add(1)?),
+    (0, minor) => Version::new(0, minor.checked_add(1)?, 0),
+    (major, _) => Version::new(major.checked_add(1)?, 0, 0),
+  })
+}
+
+// the upper bound of ~min, to write a Range back as ~
+pub(crate) fn tilde_max(min: &Version) -> Option<Version> {
+  Some(Version::new(min.major, min.minor.checked_add(1)?, 0))
+}